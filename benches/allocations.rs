@@ -0,0 +1,81 @@
+//!Counts the allocations of common logging patterns
+//!
+//!Reports are printed to stdout, while the results are printed to stderr:
+//!`cargo bench --bench allocations > /dev/null`
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use report::{Report, Level, info, warn};
+
+const ITERATIONS: usize = 10_000;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn measure(name: &str, function: impl FnOnce()) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    function();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    eprintln!("{name}: {:.2} allocations per iteration", allocations as f64 / ITERATIONS as f64);
+}
+
+fn main() {
+    measure("events", || {
+        Report::capture(|| for index in 0..ITERATIONS {
+            info!("Event {index}")
+        });
+    });
+
+    measure("filtered events", || {
+        Report::set_level(Level::Warn);
+        Report::capture(|| for index in 0..ITERATIONS {
+            info!("Event {index}")
+        });
+        Report::set_level(Level::Info);
+    });
+
+    measure("deferred events", || {
+        Report::capture(|| for index in 0..ITERATIONS {
+            Report::defer(Level::Info, move || format!("Event {index}"))
+        });
+    });
+
+    measure("groups", || {
+        Report::capture(|| for index in 0..ITERATIONS {
+            let _group = Report::rec(move || format!("Group {index}"));
+            info!("First event");
+            warn!("Second event");
+        });
+    });
+
+    measure("empty groups", || {
+        Report::capture(|| for index in 0..ITERATIONS {
+            let _group = Report::rec(move || format!("Group {index}"));
+        });
+    });
+
+    measure("reports", || for index in 0..ITERATIONS {
+        let _report = Report::log(move || format!("Report {index}"));
+        for _ in 0..8 { info!("Event") }
+    });
+}
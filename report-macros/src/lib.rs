@@ -1,9 +1,11 @@
 #![doc = include_str!("../README.md")]
 
 use proc_macro::TokenStream;
-use proc_macro2::TokenStream as TokenStream2;
-use syn::{parse_macro_input, parse_quote, parse_quote_spanned, spanned::Spanned, Attribute, Block, Error, Expr, ItemFn, ExprMacro, Result, Stmt};
-use quote::ToTokens;
+use proc_macro2::{Delimiter, Ident, Span, TokenStream as TokenStream2, TokenTree};
+use syn::{parse_macro_input, parse_quote, parse_quote_spanned, spanned::Spanned, Attribute, Block, Error, Expr, ImplItem, Item, ItemFn, ExprAwait, ExprMacro, Macro, Result, Stmt, Token};
+use syn::parse::{Parse, ParseStream, Parser};
+use syn::punctuated::Punctuated;
+use quote::{quote, ToTokens};
 ///Print all nested logging events to the console.
 ///
 ///## Usage
@@ -48,6 +50,10 @@ use quote::ToTokens;
 ///╰────────────────────────────────────────────────────────────────────────────────────────────╯
 ///```
 ///
+///Since every report is printed once it completes, the nested report is printed first.
+///To print reports in the order in which they were created, use
+///`Report::set_flush_order(Order::Fifo)`.
+///
 ///## Formatting arguments
 ///
 ///It's possible to use the arguments of the function in the format
@@ -62,19 +68,152 @@ use quote::ToTokens;
 ///}
 ///```
 ///
+///## Async functions
+///
+///Since reports are stored in thread local storage, an `async fn` could be moved to
+///another thread at every `.await`. When applied to an `async fn`, the body is therefore
+///wrapped with `report::instrument`, which carries the report along with the future.
+///
+///```
+///use report::{log, info};
+///
+///#[log("Fetching {url}")]
+///async fn fetch(url: &str) -> Result<usize, std::io::Error> {
+///    info!("Connecting");
+///    let length = std::fs::read("Cargo.toml")?.len();
+///    Ok(length + url.len())
+///}
+///```
+///
+///## Timing
+///
+///The elapsed time of the function is appended to the header of the report
+///if `Report::show_durations` is enabled. The `timing` option enables this for
+///a single report.
+///
+///```
+///use report::{log, info};
+///
+///#[log(timing, "Running task")]
+///fn task() {
+///    info!("Complementary information");
+///}
+///```
+///
+///```text
+///╭────────────────────────────────────────────────────────────────────────────────────────────╮
+///│ Running task (12ms)                                                                        │
+///├─┬──────────────────────────────────────────────────────────────────────────────────────────┤
+///│ ╰── info: Complementary information                                                        │
+///╰────────────────────────────────────────────────────────────────────────────────────────────╯
+///```
+///
+///## Conditional output
+///
+///With the `on_event` option, the report is only printed if at least one event was
+///logged. With the `on_error` option, it is only printed if at least one error was
+///logged, including the ones in nested groups.
+///
+///```
+///use report::{log, info};
+///
+///#[log(on_error, "Running task")]
+///fn task() {
+///    info!("This report is not printed, since there are no errors");
+///}
+///```
+///
+///## Output files
+///
+///With the `file` option, the report is written to the named file instead of the
+///output of all other reports. The file is created, or truncated if it already exists,
+///every time the function is called. If it can't be created, a warning is printed and
+///the report is written to stderr instead. See `Report::log_to_file` for details.
+///
+///```
+///use report::{log, info};
+///
+///#[log(file = "target/run.txt", "Running experiments")]
+///fn run() {
+///    info!("Written to target/run.txt");
+///}
+///```
+///
+///## Caller locations
+///
+///By default, the report records the location of the annotated function, which is
+///rendered in the header once `Report::show_locations` is enabled. With the `track_caller`
+///option, the function is marked with `#[track_caller]`, so that the report records the
+///location the function was called from instead. This is useful for helpers that are
+///called from many places. If the caller is marked with `#[track_caller]` as well, the
+///location of its caller is used instead.
+///
+///Since the attribute applies to the whole function, events logged directly in its body
+///and panics of calls like `unwrap` are attributed to the caller as well. Only functions
+///with the Rust ABI can be marked, and the option is rejected for `main` and `async fn`.
+///
+///The location is passed to the function as a hidden argument, so it is neither lost when
+///the function is inlined nor shared between the instantiations of a generic function.
+///Closures and async blocks in the body don't receive it, though: events logged inside of
+///a closure, for example one passed to `Iterator::map`, or inside of an async block are
+///attributed to the line in the annotated function, not to its caller.
+///
+///```
+///use report::{log, info};
+///
+///#[log(track_caller, "Loading {name}")]
+///fn load(name: &str) {
+///    info!("Attributed to the caller of load");
+///}
+///```
+///
 ///This macro should only be used in application code and not in
 ///libraries, so that a user can integrate generated reports into
 ///their own, making the grouping of related information easier.
 #[proc_macro_attribute]
 pub fn log(args: TokenStream, input: TokenStream) -> TokenStream {
     let mut item = parse_macro_input!(input as ItemFn);
-    let args = TokenStream2::from(args);
+    let (file, args) = split_value(TokenStream2::from(args), "file");
+    let (options, args) = match split_options(args, &["timing", "on_event", "on_error", "track_caller"]) {
+        Ok(split) => split,
+        Err(err) => return TokenStream::from(err.to_compile_error())
+    };
+
+    if let Some(option) = options.iter().find(|option| *option == "track_caller") {
+        if item.sig.asyncness.is_some() || item.sig.ident == "main" {
+            return TokenStream::from(Error::new(option.span(), "`track_caller` can't be used for `main` or `async fn`").to_compile_error())
+        }
+        if !item.attrs.iter().any(|attr| attr.path().is_ident("track_caller")) {
+            item.attrs.push(parse_quote!(#[track_caller]));
+        }
+    }
+
+    let options = options.iter().filter(|option| *option != "track_caller").map(|option| match option == "timing" {
+        true => quote!(.timed()),
+        false => quote!(.#option())
+    });
+    let options = quote!(#(#options)*);
 
+    if item.sig.asyncness.is_some() {
+        let block = &item.block;
+        let file = file.map(|path| quote!(.to_file(#path)));
+        item.block = parse_quote!({
+            #[allow(clippy::useless_format)]
+            ::report::instrument(async #block, || format!(#args))#file #options.await
+        });
+        return TokenStream::from(item.to_token_stream())
+    }
+
+    let logger = match file {
+        Some(path) => quote!(::report::Report::log_to_file(#path, || format!(#args))),
+        None => quote!(::report::Report::log(|| format!(#args)))
+    };
     item.block.stmts.insert(0, parse_quote!(
-        let _logger = ::report::Report::log(|| format!(#args));
+        #[allow(clippy::useless_format)]
+        let _logger = #logger #options;
     ));
 
-    return TokenStream::from(item.to_token_stream())
+    TokenStream::from(item.to_token_stream())
 }
 
 ///Annotate a new logging group with a custom message.
@@ -123,6 +262,220 @@ pub fn log(args: TokenStream, input: TokenStream) -> TokenStream {
 ///╰────────────────────────────────────────────────────────────────────────────────────────────╯
 ///```
 ///
+///## Diverging blocks
+///
+///The `else` block of a `let ... else` statement is expanded like any other block.
+///
+///```
+///use report::{report, bail, Report, Result, Level};
+///
+///#[report]
+///fn parse(input: &str) -> Result<i32> {
+///    let Ok(number) = input.parse() else {
+///        #[report("Parsing {input:?}")]
+///        bail!("Not a number")
+///    };
+///    Ok(number)
+///}
+///
+///let capture = Report::capture(|| assert!(parse("x").is_err()));
+///assert_eq!(capture.iter().collect::<Vec<_>>(), [
+///    (0, None, "Parsing \"x\""),
+///    (1, Some(Level::Error), "Not a number")
+///]);
+///```
+///
+///## Levels
+///
+///A group can be tagged with a level by passing `info`, `warn` or `error` before the
+///message. Its header is then rendered with the label and style of this level, like
+///`warning: Opening file`, to mark the nested events as the context of a warning or an error.
+///
+///```
+///use report::{report, log, warn};
+///
+///#[report]
+///#[log("Loading configuration")]
+///fn load() {
+///    #[report(warn, "Falling back to the default configuration")]
+///    warn!("The configuration file is missing");
+///}
+///```
+///
+///## Conditions
+///
+///The conditions of `if` and `while`, the scrutinees of `if let`, `while let` and `match`,
+///and the guards of `match` arms can be annotated as well. Since the annotation is followed
+///by the block of the expression, it's recommended to wrap the annotated expression in parentheses.
+///
+///```
+///use report::{report, Report, Result, Level};
+///use std::fs::File;
+///
+///fn open(path: &str) -> Result<File> {
+///    Ok(File::open(path)?)
+///}
+///
+///#[report]
+///fn read(path: &str) -> bool {
+///    if let Ok(_file) = (#[report("Opening {path}")] open(path)) {
+///        return true
+///    }
+///    false
+///}
+///
+///let capture = Report::capture(|| assert!(!read("missing.txt")));
+///let mut events = capture.iter();
+///assert_eq!(events.next(), Some((0, None, "Opening missing.txt")));
+///assert_eq!(events.next().map(|(depth, level, _)| (depth, level)), Some((1, Some(Level::Error))));
+///```
+///
+///```
+///use report::{report, Report, Result, Level};
+///
+///fn validate(value: i32) -> Result<bool> {
+///    Ok(u8::try_from(value)? > 0)
+///}
+///
+///#[report]
+///fn check(value: Option<i32>) -> Result<bool> {
+///    Ok(match value {
+///        Some(x) if (#[report("Checking {x}")] validate(x)?) => true,
+///        _ => false
+///    })
+///}
+///
+///let capture = Report::capture(|| assert!(check(Some(-1)).is_err()));
+///let mut events = capture.iter();
+///assert_eq!(events.next(), Some((0, None, "Checking -1")));
+///assert_eq!(events.next().map(|(depth, level, _)| (depth, level)), Some((1, Some(Level::Error))));
+///```
+///
+///## Macro arguments
+///
+///Arguments of macros like `vec!`, `format!` or `println!` can be annotated as well,
+///as long as they are expressions separated by commas, or an expression and a length
+///like in `vec![value; length]`. Annotations in macros with any other syntax are rejected
+///with a compile error instead of being ignored.
+///
+///```
+///use report::{report, Report, Result, Level};
+///
+///fn parse(input: &str) -> Result<i32> {
+///    Ok(input.parse()?)
+///}
+///
+///#[report]
+///fn sum() -> Result<Vec<i32>> {
+///    Ok(vec![parse("1")?, #[report("Parsing second number")] parse("x")?])
+///}
+///
+///let capture = Report::capture(|| assert!(sum().is_err()));
+///let mut events = capture.iter();
+///assert_eq!(events.next(), Some((0, None, "Parsing second number")));
+///assert_eq!(events.next().map(|(depth, level, _)| (depth, level)), Some((1, Some(Level::Error))));
+///```
+///
+///## Await expressions
+///
+///A group that is held across an `.await` point would collect the events of other
+///tasks that run on the same thread in the meantime. Annotated `.await` expressions
+///are therefore expanded with `report::instrument_group`, which only installs the
+///group while the future is polled.
+///
+///```
+///use report::{report, log, info, Result};
+///
+///async fn fetch(url: &str) -> Result<Vec<u8>> {
+///    info!("Connecting to {url}");
+///    Ok(Vec::new())
+///}
+///
+///#[report]
+///#[log("Downloading")]
+///async fn download(url: &str) -> Result {
+///    #[report("Fetching {url}")]
+///    let _data = fetch(url).await?;
+///    Ok(())
+///}
+///```
+///
+///Any other annotated expression that contains an `.await` is rejected with
+///a compile error, since the group would be held across the suspension point.
+///
+///```compile_fail
+///use report::{report, info};
+///
+///async fn fetch() {}
+///
+///#[report]
+///async fn download() {
+///    #[report("Fetching")]
+///    {
+///        fetch().await;
+///        info!("Fetched");
+///    }
+///}
+///```
+///
+///## Nested functions
+///
+///Functions and methods that are defined inside an annotated function are
+///expanded as well, so they don't need their own [`report`](macro@report) attribute.
+///
+///```
+///use report::{report, info, Report};
+///
+///#[report]
+///fn outer() {
+///    fn inner() {
+///        #[report("Inner group")]
+///        info!("This info is attached to the inner group");
+///    }
+///    inner()
+///}
+///
+///let capture = Report::capture(outer);
+///assert_eq!(capture.iter().next(), Some((0, None, "Inner group")));
+///```
+///
+///## Panics
+///
+///Reports and groups restore the state of the enclosing report when they are dropped,
+///which also happens while a panic unwinds. A report created by [`log`](macro@log) is
+///printed with the events that were logged before the panic, and once the panic is
+///caught, events are printed as if no report had been active.
+///
+///```
+///use report::{report, log, info, Report};
+///use std::panic::catch_unwind;
+///use std::sync::{Arc, Mutex};
+///
+///#[report]
+///#[log("Running task")]
+///fn task() {
+///    #[report("Outer group")]
+///    step();
+///}
+///
+///#[report]
+///fn step() {
+///    info!("Before the panic");
+///    #[report("Inner group")]
+///    panic!("Failure")
+///}
+///
+///let output = Arc::new(Mutex::new(Vec::new()));
+///let sink = output.clone();
+///Report::set_writer(move |text| sink.lock().unwrap().push(text.to_string()));
+///
+///assert!(catch_unwind(task).is_err());
+///assert!(output.lock().unwrap()[0].contains("info: Before the panic"));
+///
+///info!("Printed immediately");
+///assert_eq!(output.lock().unwrap()[1], "info: Printed immediately\n");
+///```
+///
 ///## Borrowing of format arguments
 ///
 ///Just like any other macro in this crate, the format string used by
@@ -160,7 +513,22 @@ pub fn log(args: TokenStream, input: TokenStream) -> TokenStream {
 ///    Ok(())
 ///}
 ///```
-
+///
+///If the cost of formatting is acceptable, the `eager` option formats the message
+///before the expression is evaluated, so the arguments can be moved afterwards.
+///
+///```
+///use report::{Result, report};
+///use std::fs::File;
+///
+///#[report]
+///fn open_file() -> Result {
+///    let path = String::from("Cargo.toml");
+///    #[report(eager, "Opening file {path:?}")]
+///    let _file = File::open(path)?; //this works because the message is already formatted
+///    Ok(())
+///}
+///```
 #[proc_macro_attribute]
 pub fn report(args: TokenStream, input: TokenStream) -> TokenStream {
 
@@ -178,7 +546,113 @@ pub fn report(args: TokenStream, input: TokenStream) -> TokenStream {
         return TokenStream::from(err.to_compile_error())
     }
 
-    return TokenStream::from(item.to_token_stream())
+    TokenStream::from(item.to_token_stream())
+}
+
+///Expands the `event`, `info`, `warn` and `error` macros of the report crate
+///
+///**This macro should not be used directly**
+#[doc(hidden)]
+#[proc_macro]
+pub fn event(input: TokenStream) -> TokenStream {
+    match expand_event(TokenStream2::from(input)) {
+        Ok(tokens) => TokenStream::from(tokens),
+        Err(err) => TokenStream::from(err.to_compile_error())
+    }
+}
+
+struct Field {
+    key: Ident,
+    format: &'static str,
+    value: Expr
+}
+
+impl Parse for Field {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let sigil = |input: ParseStream| -> Result<&'static str> {
+            if input.peek(Token![%]) {
+                input.parse::<Token![%]>()?;
+            } else if input.peek(Token![?]) {
+                input.parse::<Token![?]>()?;
+                return Ok("{:?}")
+            }
+            Ok("{}")
+        };
+
+        if input.peek(syn::Ident) && input.peek2(Token![=]) {
+            let key = input.parse()?;
+            input.parse::<Token![=]>()?;
+            let format = sigil(input)?;
+            return Ok(Field { key, format, value: input.parse()? })
+        }
+
+        let format = sigil(input)?;
+        let key: Ident = input.parse()?;
+        Ok(Field { value: parse_quote!(#key), key, format })
+    }
+}
+
+fn expand_event(input: TokenStream2) -> Result<TokenStream2> {
+    let mut tokens = input.into_iter();
+    let is_separator = |token: &TokenTree| matches!(token, TokenTree::Punct(punct) if punct.as_char() == ';');
+
+    let level: TokenStream2 = tokens.by_ref().take_while(|token| !is_separator(token)).collect();
+    if level.is_empty() {
+        return Err(Error::new(Span::call_site(), "Expected a level"))
+    }
+
+    let message: TokenStream2 = tokens.by_ref().take_while(|token| !is_separator(token)).collect();
+    let location = quote!(::std::panic::Location::caller());
+    let fields: TokenStream2 = tokens.collect();
+    if fields.is_empty() {
+        return Ok(quote!(::report::Report::event_in(::std::module_path!(), #level, #location, format_args!(#message), &[])))
+    }
+
+    let fields = Punctuated::<Field, Token![,]>::parse_terminated.parse2(fields)?;
+    let fields = fields.iter().map(|Field { key, format, value }| {
+        let key = key.to_string();
+        quote!((#key, format_args!(#format, #value)))
+    });
+
+    Ok(quote!(::report::Report::event_in(::std::module_path!(), #level, #location, format_args!(#message), &[#(#fields),*])))
+}
+
+fn split_options(args: TokenStream2, known: &[&str]) -> Result<(Vec<Ident>, TokenStream2)> {
+    let tokens: Vec<TokenTree> = args.into_iter().collect();
+    let mut rest = tokens.as_slice();
+    let mut options = Vec::new();
+
+    while let [TokenTree::Ident(ident), TokenTree::Punct(punct), remainder @ ..] = rest {
+        if punct.as_char() != ',' { break }
+        if !known.iter().any(|option| ident == option) {
+            return Err(Error::new(ident.span(), format!("Unknown option `{ident}`")))
+        }
+        options.push(ident.clone());
+        rest = remainder;
+    }
+
+    Ok((options, rest.iter().cloned().collect()))
+}
+
+fn split_value(args: TokenStream2, name: &str) -> (Option<TokenTree>, TokenStream2) {
+    let mut tokens: Vec<TokenTree> = args.into_iter().collect();
+    let mut index = 0;
+
+    while let [TokenTree::Ident(ident), TokenTree::Punct(punct), rest @ ..] = &tokens[index..] {
+        match punct.as_char() {
+            ',' => index += 2,
+            '=' if ident == name => {
+                let [value, TokenTree::Punct(comma), ..] = rest else { break };
+                if comma.as_char() != ',' { break }
+                let value = value.clone();
+                tokens.drain(index..index + 4);
+                return (Some(value), tokens.into_iter().collect())
+            },
+            _ => break
+        }
+    }
+
+    (None, tokens.into_iter().collect())
 }
 
 fn process_expr(expr: &mut Expr, local_attrs: Option<&mut Vec<Attribute>>) -> Result<()> {
@@ -203,14 +677,84 @@ fn process_expr(expr: &mut Expr, local_attrs: Option<&mut Vec<Attribute>>) -> Re
     }
 
     for attr in attrs {
-        let list = attr.meta.require_list()?.tokens.clone(); 
-        *expr = parse_quote_spanned!(attr.span() => {
-            let _logger = ::report::Report::rec(|| format!(#list));
-            #expr
-        });
+        let list = attr.meta.require_list()?.tokens.clone();
+        let (options, list) = split_options(list, &["eager", "info", "warn", "error"])?;
+        let eager = options.iter().any(|option| option == "eager");
+        let level = options.iter().find_map(|option| match option.to_string().as_str() {
+            "info" => Some(quote!(.level(::report::Level::Info))),
+            "warn" => Some(quote!(.level(::report::Level::Warn))),
+            "error" => Some(quote!(.level(::report::Level::Error))),
+            _ => None
+        }).unwrap_or_default();
+
+        if let Some(await_expr) = find_await(expr) {
+            let base = &await_expr.base;
+            let message = match eager {
+                false => quote!(|| format!(#list)),
+                true => quote!({ let message = format!(#list); move || message.clone() })
+            };
+            *await_expr.base = parse_quote_spanned!(attr.span() =>
+                ::report::instrument_group(#base, #[allow(clippy::useless_format)] #message)#level
+            );
+            continue
+        }
+
+        if contains_await(expr.to_token_stream()) {
+            return Err(Error::new(attr.span(), "A report can't be held across `.await`, annotate the `.await` expression itself or use `report::instrument`"))
+        }
+
+        *expr = if eager {
+            parse_quote_spanned!(attr.span() => {
+                #[allow(clippy::useless_format)]
+                let _logger = ::report::Report::rec_eager(format!(#list))#level;
+                #expr
+            })
+        } else {
+            parse_quote_spanned!(attr.span() => {
+                #[allow(clippy::useless_format)]
+                let _logger = ::report::Report::rec(|| format!(#list))#level;
+                #expr
+            })
+        };
+    }
+
+    Ok(())
+}
+
+fn find_await(expr: &mut Expr) -> Option<&mut ExprAwait> {
+    match expr {
+        Expr::Await(await_expr) => Some(await_expr),
+        Expr::Try(try_expr) => find_await(&mut try_expr.expr),
+        _ => None
     }
+}
 
-    return Ok(());
+fn contains_await(tokens: TokenStream2) -> bool {
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == '.' && matches!(tokens.peek(), Some(TokenTree::Ident(ident)) if ident == "await") => return true,
+            TokenTree::Ident(ident) if ident == "async" => {
+                tokens.next_if(|token| matches!(token, TokenTree::Ident(ident) if ident == "move"));
+                tokens.next_if(|token| matches!(token, TokenTree::Group(..)));
+            },
+            TokenTree::Group(group) if contains_await(group.stream()) => return true,
+            _ => ()
+        }
+    }
+    false
+}
+
+fn process_scrutinee(expr: &mut Expr) -> Result<()> {
+    process_expr(expr, None)?;
+
+    if let Expr::Paren(paren_expr) = expr {
+        if paren_expr.attrs.is_empty() && matches!(*paren_expr.expr, Expr::Block(..)) {
+            *expr = (*paren_expr.expr).clone();
+        }
+    }
+
+    Ok(())
 }
 
 fn iter_block(block: &mut Block) -> Result<()> {
@@ -219,7 +763,7 @@ fn iter_block(block: &mut Block) -> Result<()> {
             Stmt::Local(local) => if let Some(init) = local.init.as_mut() {
                 process_expr(&mut init.expr, Some(&mut local.attrs))?;
                 if let Some((.., expr)) = init.diverge.as_mut() {
-                    iter_expr(expr)?;
+                    process_expr(expr, None)?;
                 }
             },
             Stmt::Expr(expr, ..) => {
@@ -234,7 +778,10 @@ fn iter_block(block: &mut Block) -> Result<()> {
                     res
                 });
 
-                if attrs.is_empty() { continue } 
+                if attrs.is_empty() {
+                    iter_macro(&mut macro_expr.mac)?;
+                    continue
+                }
                 let mut expr = Expr::Macro(ExprMacro {
                     attrs,
                     mac: macro_expr.mac.clone()
@@ -243,6 +790,12 @@ fn iter_block(block: &mut Block) -> Result<()> {
                 process_expr(&mut expr, None)?;
                 *statement = Stmt::Expr(expr, macro_expr.semi_token)
             },
+            Stmt::Item(Item::Fn(item_fn)) => iter_block(&mut item_fn.block)?,
+            Stmt::Item(Item::Impl(item_impl)) => for item in item_impl.items.iter_mut() {
+                if let ImplItem::Fn(impl_fn) = item {
+                    iter_block(&mut impl_fn.block)?
+                }
+            },
             Stmt::Item(..) => ()
         }
     }
@@ -265,7 +818,7 @@ fn iter_expr(expr: &mut Expr) -> Result<()> {
             }
             Ok(())
         },
-        Expr::Macro(..) => Ok(()),
+        Expr::Macro(macro_expr) => iter_macro(&mut macro_expr.mac),
         Expr::MethodCall(method_call_expr) => {
             process_expr(&mut method_call_expr.receiver, None)?;
             for arg in method_call_expr.args.iter_mut() {
@@ -274,8 +827,11 @@ fn iter_expr(expr: &mut Expr) -> Result<()> {
             Ok(())
         },
         Expr::Match(match_expr) => {
-            process_expr(&mut match_expr.expr, None)?;
+            process_scrutinee(&mut match_expr.expr)?;
             for arm in match_expr.arms.iter_mut() {
+                if let Some((_, guard)) = arm.guard.as_mut() {
+                    process_scrutinee(guard)?;
+                }
                 process_expr(arm.body.as_mut(), Some(arm.attrs.as_mut()))?;
             }
             Ok(())
@@ -336,19 +892,19 @@ fn iter_expr(expr: &mut Expr) -> Result<()> {
             iter_block(&mut for_loop_expr.body)
         },
         Expr::While(while_expr) => {
-            process_expr(&mut while_expr.cond, None)?;
+            process_scrutinee(&mut while_expr.cond)?;
             iter_block(&mut while_expr.body)
         },
         Expr::Loop(loop_expr) => iter_block(&mut loop_expr.body),
         Expr::If(if_expr) => {
-            process_expr(&mut if_expr.cond, None)?;
+            process_scrutinee(&mut if_expr.cond)?;
             iter_block(&mut if_expr.then_branch)?;
             if let Some((_, else_branch)) = if_expr.else_branch.as_mut() {
                 process_expr(else_branch, None)?;
             }
             Ok(())
         },
-        Expr::Let(let_expr) => process_expr(&mut let_expr.expr, None),
+        Expr::Let(let_expr) => process_scrutinee(&mut let_expr.expr),
         Expr::Lit(..) => Ok(()),
         Expr::Cast(cast_expr) => {
             process_expr(&mut cast_expr.expr, None)?;
@@ -371,6 +927,47 @@ fn iter_expr(expr: &mut Expr) -> Result<()> {
     }
 }
 
+fn iter_macro(mac: &mut Macro) -> Result<()> {
+    let Some(span) = find_report(mac.tokens.clone()) else { return Ok(()) };
+
+    if let Ok(mut args) = mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+        for arg in args.iter_mut() {
+            process_expr(arg, None)?;
+        }
+        mac.tokens = args.into_token_stream();
+    } else if let Ok((mut expr, semi, mut len)) = mac.parse_body_with(|input: ParseStream| {
+        Ok((input.parse::<Expr>()?, input.parse::<Token![;]>()?, input.parse::<Expr>()?))
+    }) {
+        process_expr(&mut expr, None)?;
+        process_expr(&mut len, None)?;
+        mac.tokens = quote!(#expr #semi #len);
+    } else {
+        return Err(Error::new(span, "report attributes are only supported in macros whose arguments are expressions separated by commas"))
+    }
+
+    Ok(())
+}
+
+fn find_report(tokens: TokenStream2) -> Option<Span> {
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == '#' => {
+                let Some(TokenTree::Group(group)) = tokens.peek() else { continue };
+                let is_report = matches!(group.stream().into_iter().next(), Some(TokenTree::Ident(ident)) if ident == "report");
+                if group.delimiter() == Delimiter::Bracket && is_report {
+                    return Some(punct.span())
+                }
+            },
+            TokenTree::Group(group) => if let Some(span) = find_report(group.stream()) {
+                return Some(span)
+            },
+            _ => ()
+        }
+    }
+    None
+}
+
 fn get_attrs(expr: &mut Expr) -> Option<&mut Vec<Attribute>> {
     match expr {
         Expr::Try(try_expr) => Some(&mut try_expr.attrs),
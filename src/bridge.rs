@@ -0,0 +1,92 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use log::{Level, Log, Metadata, Record, SetLoggerError, LevelFilter};
+use crate::{Report, ReportConfig};
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+///Logger that forwards records of the `log` crate to the active report
+///
+///Records are rendered with their target, which defaults to the module path.
+///Since this crate has no separate `debug` and `trace` levels, those records
+///are logged as infos. Records below the level set with
+///[`Report::set_level`](crate::Report::set_level) are discarded. Like any other
+///event, records are printed immediately if there is no active report. Records
+///are not annotated with a source location, but their module path is passed to
+///the filter set with [`Report::set_module_filter`](crate::Report::set_module_filter).
+///
+///# Example
+///```
+///use report::{init_log_bridge, Report, Level};
+///
+///init_log_bridge().unwrap();
+///Report::set_level(Level::Warn);
+///assert_eq!(log::max_level(), log::LevelFilter::Warn);
+///
+///let capture = Report::capture(|| {
+///    log::debug!(target: "parser", "Filtered");
+///    log::warn!(target: "parser", "Forwarded");
+///});
+///assert_eq!(capture.iter().collect::<Vec<_>>(), [(0, Some(Level::Warn), "parser: Forwarded")]);
+///
+///Report::set_level(Level::Info);
+///let capture = Report::capture(|| log::trace!(target: "parser", "Forwarded"));
+///assert_eq!(capture.iter().collect::<Vec<_>>(), [(0, Some(Level::Info), "parser: Forwarded")]);
+///```
+pub struct LogBridge;
+
+///Installs the [`LogBridge`] as the global logger of the `log` crate
+///
+///The maximum level of the `log` crate follows the level set with
+///[`Report::set_level`](crate::Report::set_level), so that discarded records are
+///skipped by the macros of the `log` crate before they are formatted. This fails
+///if another logger was already installed.
+///
+///# Example
+///```
+///use report::{init_log_bridge, log};
+///
+///#[log("Running task")]
+///fn main() {
+///    init_log_bridge().unwrap();
+///    log::warn!("Forwarded to the report");
+///}
+///```
+pub fn init_log_bridge() -> Result<(), SetLoggerError> {
+    log::set_logger(&LogBridge)?;
+    INSTALLED.store(true, Ordering::Relaxed);
+    set_max_level(ReportConfig::get().level);
+    Ok(())
+}
+
+pub(crate) fn set_max_level(level: crate::Level) {
+    if !INSTALLED.load(Ordering::Relaxed) { return }
+    log::set_max_level(match level {
+        crate::Level::Info => LevelFilter::Trace,
+        crate::Level::Warn => LevelFilter::Warn,
+        crate::Level::Error => LevelFilter::Error
+    })
+}
+
+impl Log for LogBridge {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        ReportConfig::enabled(convert(metadata.level()))
+    }
+
+    fn log(&self, record: &Record) {
+        let level = convert(record.level());
+        if record.module_path().is_some_and(|module| !ReportConfig::accepts(module)) { return }
+        let target = record.target();
+        let message = record.args();
+        Report::event(level, None, format_args!("{target}: {message}"), &[])
+    }
+
+    fn flush(&self) {}
+}
+
+fn convert(level: Level) -> crate::Level {
+    match level {
+        Level::Error => crate::Level::Error,
+        Level::Warn => crate::Level::Warn,
+        Level::Info | Level::Debug | Level::Trace => crate::Level::Info
+    }
+}
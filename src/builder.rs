@@ -0,0 +1,151 @@
+use std::panic::Location;
+use std::time::Instant;
+use std::sync::atomic::Ordering;
+use crate::{Action, Condition, Counts, Event, Level, Message, Report, ReportConfig, Root, Target, SEQUENCE};
+
+///Report that is built explicitly instead of through thread local storage
+///
+///Events are added with methods instead of the [`info`](macro@crate::info),
+///[`warn`](macro@crate::warn) and [`error`](macro@crate::error) macros, and
+///are stored in the builder itself. Nothing happens when the builder is dropped;
+///it is only rendered by [`Builder::render`] or printed by [`Builder::print`].
+///This gives full control over the lifetime of a report, for example in loops
+///where the guards created by [`log`](macro@crate::log) would be costly.
+///Events logged with the macros are not collected by a builder.
+///
+///# Example
+///```
+///use report::Builder;
+///
+///let mut report = Builder::new("Running task");
+///report.info("Complementary information");
+///report.group("Opening file", |group| {
+///    group.warn("File is empty");
+///});
+///report.print();
+///```
+pub struct Builder {
+    message: String,
+    actions: Vec<Action>,
+    location: &'static Location<'static>,
+    start: Instant
+}
+
+impl Builder {
+
+    ///Creates an empty report with the given header
+    ///
+    ///The location of the caller is recorded, so that it can be
+    ///rendered if [`Report::show_locations`] is enabled.
+    #[track_caller]
+    pub fn new(message: impl Into<String>) -> Self {
+        Builder {
+            message: message.into(),
+            actions: Vec::new(),
+            location: Location::caller(),
+            start: Instant::now()
+        }
+    }
+
+    ///Adds an event with the `info` prefix
+    #[track_caller]
+    pub fn info(&mut self, message: impl Into<String>) -> &mut Self {
+        self.event(Level::Info, message)
+    }
+
+    ///Adds an event with the `warning` prefix
+    #[track_caller]
+    pub fn warn(&mut self, message: impl Into<String>) -> &mut Self {
+        self.event(Level::Warn, message)
+    }
+
+    ///Adds an event with the `error` prefix
+    #[track_caller]
+    pub fn error(&mut self, message: impl Into<String>) -> &mut Self {
+        self.event(Level::Error, message)
+    }
+
+    ///Adds an event with the given level
+    ///
+    ///Events below the level set with [`Report::set_level`] are discarded.
+    #[track_caller]
+    pub fn event(&mut self, level: Level, message: impl Into<String>) -> &mut Self {
+        if ReportConfig::enabled(level) {
+            let event = Event {
+                message: Message::Text(message.into()),
+                fields: Vec::new(),
+                location: Some(Location::caller()),
+                backtrace: Event::get_backtrace(level)
+            };
+            self.actions.push(Action::new(level, event))
+        }
+        self
+    }
+
+    ///Adds a nested group with the events added by the closure
+    ///
+    ///Like the groups created by [`report`](macro@crate::report),
+    ///the group is omitted if the closure adds no events.
+    ///
+    ///# Example
+    ///```
+    ///use report::Builder;
+    ///
+    ///let mut report = Builder::new("Loading configuration");
+    ///for path in ["a.toml", "b.toml"] {
+    ///    report.group(format!("Reading {path}"), |group| {
+    ///        group.info("Found 3 keys");
+    ///    });
+    ///}
+    ///assert!(report.render().contains("Reading b.toml"));
+    ///```
+    pub fn group(&mut self, message: impl Into<String>, function: impl FnOnce(&mut Builder)) -> &mut Self {
+        let mut group = Builder {
+            message: message.into(),
+            actions: Vec::new(),
+            location: self.location,
+            start: Instant::now()
+        };
+
+        function(&mut group);
+
+        if !group.actions.is_empty() {
+            self.actions.push(Action::Report {
+                message: group.message,
+                actions: group.actions,
+                elapsed: group.start.elapsed(),
+                level: None
+            })
+        }
+        self
+    }
+
+    ///Renders the report with the active configuration
+    ///
+    ///The output is the same as the one of a report created by [`log`](macro@crate::log),
+    ///but it is returned instead of printed. Digests are not emitted.
+    pub fn render(self) -> String {
+        let Builder { message, mut actions, location, start } = self;
+        let config = ReportConfig::get();
+        let duration = config.durations.then_some(start.elapsed());
+        let counts = Counts::of(&actions);
+        Report::render(&config, &message, location, duration, counts, &mut actions)
+    }
+
+    ///Prints the report like a report created by [`log`](macro@crate::log)
+    ///
+    ///The report is written to the configured output and taken into account by
+    ///[`exit_code`](crate::exit_code) and the digest sink.
+    pub fn print(self) {
+        let root = Root {
+            message: self.message,
+            location: self.location,
+            start: self.start,
+            timing: false,
+            condition: Condition::Always,
+            sequence: SEQUENCE.fetch_add(1, Ordering::Relaxed),
+            target: Target::Default
+        };
+        Report::finish(root, self.actions)
+    }
+}
@@ -0,0 +1,189 @@
+use std::panic::Location;
+use std::slice::Iter as SliceIter;
+use std::time::{Duration, Instant};
+use std::sync::atomic::Ordering;
+use crate::{Action, Condition, Level, Report, Root, Scope, Target, ACTIONS, ACTIVE, SEQUENCE};
+
+///Events collected by [`Report::capture`]
+///
+///The events are not printed, but can be inspected by iterating over
+///the captured tree. Every item consists of the nesting depth, the level
+///and the message of an event. Group headers are included with a level of `None`.
+///Deferred messages are formatted when the capture is created.
+pub struct Capture {
+    pub(crate) actions: Vec<Action>,
+    pub(crate) elapsed: Duration
+}
+
+///Report that was stored by [`Sink::Ring`](crate::Sink::Ring)
+///
+///See [`drain_roots`](crate::drain_roots).
+pub struct StoredReport {
+    ///Message of the report
+    pub message: String,
+    ///Time between the creation and the drop of the report
+    pub elapsed: Duration,
+    ///Events of the report, including nested groups
+    pub events: Capture
+}
+
+///Depth-first iterator over a [`Capture`]
+pub struct Iter<'a> {
+    stack: Vec<SliceIter<'a, Action>>
+}
+
+impl Report<fn() -> String> {
+
+    ///Collects all events logged by the closure without printing them
+    ///
+    ///Calls to [`flush`](crate::flush) inside of the closure have no effect.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, Level, info, warn};
+    ///
+    ///let capture = Report::capture(|| {
+    ///    let _group = Report::rec(|| format!("Group"));
+    ///    info!("Nested information");
+    ///    warn!("Nested warning");
+    ///});
+    ///
+    ///let events: Vec<_> = capture.iter().collect();
+    ///assert_eq!(events, [
+    ///    (0, None, "Group"),
+    ///    (1, Some(Level::Info), "Nested information"),
+    ///    (1, Some(Level::Warn), "Nested warning")
+    ///]);
+    ///```
+    ///
+    ///The state is restored if the closure panics:
+    ///```
+    ///use report::{Report, info};
+    ///use std::panic::catch_unwind;
+    ///use std::sync::{Arc, Mutex};
+    ///
+    ///let output = Arc::new(Mutex::new(String::new()));
+    ///let sink = output.clone();
+    ///Report::set_writer(move |text| sink.lock().unwrap().push_str(text));
+    ///
+    ///assert!(catch_unwind(|| Report::capture(|| panic!("Failure"))).is_err());
+    ///info!("Printed immediately");
+    ///assert_eq!(*output.lock().unwrap(), "info: Printed immediately\n");
+    ///```
+    pub fn capture(function: impl FnOnce()) -> Capture {
+        let scope = Scope::enter(Vec::new(), None);
+        let start = Instant::now();
+        function();
+        let elapsed = start.elapsed();
+        let (mut actions, _) = scope.exit();
+        actions.iter_mut().for_each(Action::resolve);
+        Capture { actions, elapsed }
+    }
+
+    ///Attaches the events of a capture to the active report as a group
+    ///
+    ///This makes it possible to run subtasks separately and combine their events
+    ///into a single report afterwards. Like any other group, it is omitted if the
+    ///capture is empty. If there is no active report, the group is printed
+    ///immediately as a report of its own.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, Level, info, warn};
+    ///
+    ///let first = Report::capture(|| info!("First subtask"));
+    ///let second = Report::capture(|| warn!("Second subtask"));
+    ///
+    ///let capture = Report::capture(|| {
+    ///    Report::attach(String::from("Running first subtask"), first);
+    ///    Report::attach(String::from("Running second subtask"), second);
+    ///    Report::attach(String::from("Omitted"), Report::capture(|| ()));
+    ///});
+    ///
+    ///assert_eq!(capture.iter().collect::<Vec<_>>(), [
+    ///    (0, None, "Running first subtask"),
+    ///    (1, Some(Level::Info), "First subtask"),
+    ///    (0, None, "Running second subtask"),
+    ///    (1, Some(Level::Warn), "Second subtask")
+    ///]);
+    ///```
+    #[track_caller]
+    pub fn attach(label: String, capture: Capture) {
+        let Capture { actions, elapsed } = capture;
+        if actions.is_empty() { return }
+
+        if !ACTIVE.get() {
+            let root = Root {
+                message: label,
+                location: Location::caller(),
+                start: Instant::now().checked_sub(elapsed).unwrap_or_else(Instant::now),
+                timing: false,
+                condition: Condition::Always,
+                sequence: SEQUENCE.fetch_add(1, Ordering::Relaxed),
+                target: Target::Default
+            };
+            return Report::finish(root, actions)
+        }
+
+        let action = Action::Report { message: label, actions, elapsed, level: None };
+        ACTIONS.with_borrow_mut(|actions| actions.push(action))
+    }
+}
+
+impl Capture {
+
+    ///Returns an iterator over all events, including nested group headers
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { stack: vec![self.actions.iter()] }
+    }
+
+    ///Calls the closure with the depth, level and message of every event
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, error};
+    ///
+    ///let capture = Report::capture(|| error!("Failure"));
+    ///let mut errors = 0;
+    ///capture.visit(|_, level, _| if level.is_some() { errors += 1 });
+    ///assert_eq!(errors, 1);
+    ///```
+    pub fn visit(&self, mut visitor: impl FnMut(usize, Option<Level>, &str)) {
+        for (depth, level, message) in self.iter() {
+            visitor(depth, level, message)
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Capture {
+    type Item = (usize, Option<Level>, &'a str);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (usize, Option<Level>, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let depth = self.stack.len().checked_sub(1)?;
+            let Some(action) = self.stack[depth].next() else {
+                self.stack.pop();
+                continue
+            };
+
+            return Some(match action {
+                Action::Report { message, actions, .. } => {
+                    self.stack.push(actions.iter());
+                    (depth, None, message.as_str())
+                },
+                Action::Info(event) => (depth, Some(Level::Info), event.message.as_str()),
+                Action::Warn(event) => (depth, Some(Level::Warn), event.message.as_str()),
+                Action::Error(event) => (depth, Some(Level::Error), event.message.as_str())
+            })
+        }
+    }
+}
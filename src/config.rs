@@ -0,0 +1,474 @@
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+use console::Style;
+use crate::{Digest, Level, Theme};
+
+pub(crate) type Writer = Arc<Mutex<dyn FnMut(&str) + Send>>;
+pub(crate) type DigestFormat = Arc<Mutex<dyn Fn(&Digest) -> String + Send>>;
+pub(crate) type ModuleFilter = Box<dyn Fn(&str) -> bool + Send>;
+
+static CONFIG: OnceLock<Mutex<ReportConfig>> = OnceLock::new();
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+static BACKTRACE: AtomicBool = AtomicBool::new(false);
+static FILTERED: AtomicBool = AtomicBool::new(false);
+static AUTOLOG: AtomicBool = AtomicBool::new(true);
+
+///Output format of reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    ///Human readable tree, which is optionally framed
+    #[default]
+    Human,
+    ///One JSON object per report in the form
+    ///`{"message": ..., "events": [{"level": "info", "message": ...}, {"message": ..., "events": [...]}]}`,
+    ///where nested groups are represented by objects with their own `events`.
+    ///Events with fields additionally contain an object like `"fields": {"path": ...}`,
+    ///and groups with a [level](crate::Report::level) contain its name in `"level"`.
+    ///Events outside of a report are printed as a single event object per line
+    #[cfg(feature = "json")]
+    Json,
+    ///One HTML fragment per report in the form
+    ///`<div class="report"><p class="report-header">...</p><ul>...</ul></div>`,
+    ///where events are list items with the class `report-info`, `report-warn` or `report-error`,
+    ///and nested groups are list items with the class `report-group` that contain their own list.
+    ///Messages are wrapped in `<span class="report-message">`, fields in `<span class="report-field">`,
+    ///and all text is escaped, so the styling is left to the page.
+    ///Events outside of a report are printed as a single list item per line
+    #[cfg(feature = "html")]
+    Html
+}
+
+///Handling of lines that are wider than the frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    ///Cut the line off and end it with `...`
+    #[default]
+    Truncate,
+    ///Continue the line in the next row, aligned with the start of the message
+    Wrap
+}
+
+///Order of the events in a group
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Sort {
+    ///Keep the order in which the events were logged
+    #[default]
+    None,
+    ///Move errors before warnings and warnings before infos, while keeping
+    ///the order within a level and the position of nested groups
+    SeverityDesc
+}
+
+///Order in which reports are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Order {
+    ///Print every report as soon as it completes, so nested reports
+    ///are printed before the reports that enclose them
+    #[default]
+    Lifo,
+    ///Queue reports that complete inside of another report and print them
+    ///in the order in which they were created once the outermost report completes
+    Fifo
+}
+
+///Destination of completed reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Sink {
+    ///Render reports and write them to the output
+    #[default]
+    Output,
+    ///Store the events of reports without rendering them, so that they can be retrieved
+    ///with [`drain_roots`](crate::drain_roots). Once `capacity` reports are stored,
+    ///the oldest one is discarded for every new report.
+    Ring {
+        ///Maximum number of stored reports
+        capacity: usize
+    }
+}
+
+///Configuration shared by all reports
+///
+///All options are collected in this type, which can be constructed with
+///the builder methods below and applied at once with [`Report::configure`](crate::Report::configure).
+///This replaces the whole configuration atomically, so other threads never observe
+///a partially applied configuration. The individual setters on [`Report`](crate::Report)
+///modify the active configuration in place.
+///
+///Options given to an individual report, for example through the arguments of the
+///[`log`](macro@crate::log) attribute, take precedence over this configuration.
+///
+///# Example
+///```
+///use report::{Report, ReportConfig};
+///
+///Report::configure(ReportConfig::new()
+///    .locations(true)
+///    .digest_sink(|line| eprintln!("{line}")));
+///```
+pub struct ReportConfig {
+    pub(crate) format: Format,
+    pub(crate) level: Level,
+    pub(crate) theme: Theme,
+    pub(crate) indent: usize,
+    pub(crate) styles: [LevelStyle; 3],
+    pub(crate) header_style: Style,
+    pub(crate) separator: bool,
+    pub(crate) compact: bool,
+    pub(crate) color: Option<bool>,
+    pub(crate) icons: bool,
+    pub(crate) dedup: bool,
+    pub(crate) overflow: Overflow,
+    pub(crate) max_width: Option<usize>,
+    pub(crate) fallback_width: Option<usize>,
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) sort: Sort,
+    pub(crate) order: Order,
+    pub(crate) sink: Sink,
+    pub(crate) locations: bool,
+    pub(crate) durations: bool,
+    pub(crate) thread: bool,
+    pub(crate) counts: bool,
+    pub(crate) backtrace: bool,
+    pub(crate) autolog: bool,
+    pub(crate) writer: Option<Writer>,
+    pub(crate) digest_sink: Option<Writer>,
+    pub(crate) digest_format: Option<DigestFormat>,
+    pub(crate) module_filter: Option<ModuleFilter>
+}
+
+impl ReportConfig {
+
+    ///Creates the default configuration, which matches the behavior
+    ///of a report that was never configured
+    pub fn new() -> Self {
+        Self {
+            format: Format::Human,
+            level: Level::Info,
+            theme: Theme::new(),
+            indent: 4,
+            styles: [
+                LevelStyle { label: String::from("info"), style: Style::new().blue() },
+                LevelStyle { label: String::from("warning"), style: Style::new().yellow() },
+                LevelStyle { label: String::from("error"), style: Style::new().red() }
+            ],
+            header_style: Style::new(),
+            separator: true,
+            compact: false,
+            color: None,
+            icons: false,
+            dedup: false,
+            overflow: Overflow::Truncate,
+            max_width: None,
+            fallback_width: None,
+            max_depth: None,
+            sort: Sort::None,
+            order: Order::Lifo,
+            sink: Sink::Output,
+            locations: false,
+            durations: false,
+            thread: false,
+            counts: false,
+            backtrace: false,
+            autolog: true,
+            writer: None,
+            digest_sink: None,
+            digest_format: None,
+            module_filter: None
+        }
+    }
+
+    ///Sets the output format of reports
+    ///
+    ///See [`Report::set_format`](crate::Report::set_format).
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    ///Sets the minimum level of events
+    ///
+    ///See [`Report::set_level`](crate::Report::set_level).
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    ///Sets the glyphs used to draw reports
+    ///
+    ///See [`Theme`].
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    ///Sets the width of the indentation of nested events
+    ///
+    ///See [`Report::set_indent`](crate::Report::set_indent).
+    pub fn indent(mut self, width: usize) -> Self {
+        self.indent = width;
+        self
+    }
+
+    ///Sets the label and style of a level
+    ///
+    ///See [`Report::set_style`](crate::Report::set_style).
+    pub fn style(mut self, level: Level, style: Style, label: &str) -> Self {
+        self.styles[level as usize] = LevelStyle { label: label.to_string(), style };
+        self
+    }
+
+    ///Sets the style of the header of reports
+    ///
+    ///See [`Report::set_header_style`](crate::Report::set_header_style).
+    pub fn header_style(mut self, style: Style) -> Self {
+        self.header_style = style;
+        self
+    }
+
+    ///Enables or disables the compact rendering of reports with a single event
+    ///
+    ///See [`Report::set_compact`](crate::Report::set_compact).
+    pub fn compact(mut self, compact: bool) -> Self {
+        self.compact = compact;
+        self
+    }
+
+    ///Enables or disables the row between the header and the events of a report
+    ///
+    ///See [`Report::show_separator`](crate::Report::show_separator).
+    pub fn separator(mut self, show: bool) -> Self {
+        self.separator = show;
+        self
+    }
+
+    ///Overrides whether colors are used
+    ///
+    ///See [`Report::set_color`](crate::Report::set_color).
+    pub fn color(mut self, color: Option<bool>) -> Self {
+        self.color = color;
+        self
+    }
+
+    ///Enables or disables icons in place of the labels of events
+    ///
+    ///See [`Report::set_icons`](crate::Report::set_icons).
+    pub fn icons(mut self, icons: bool) -> Self {
+        self.icons = icons;
+        self
+    }
+
+    ///Enables or disables the collapsing of repeated events
+    ///
+    ///See [`Report::set_dedup`](crate::Report::set_dedup).
+    pub fn dedup(mut self, dedup: bool) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    ///Sets the handling of lines that are wider than the frame
+    ///
+    ///See [`Report::set_overflow`](crate::Report::set_overflow).
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    ///Sets the maximum width of the frame
+    ///
+    ///See [`Report::set_max_width`](crate::Report::set_max_width).
+    pub fn max_width(mut self, width: Option<usize>) -> Self {
+        self.max_width = width;
+        self
+    }
+
+    ///Sets the width of the frame if the size of the terminal is unknown
+    ///
+    ///See [`Report::set_fallback_width`](crate::Report::set_fallback_width).
+    pub fn fallback_width(mut self, width: Option<usize>) -> Self {
+        self.fallback_width = width;
+        self
+    }
+
+    ///Sets the number of levels of nested groups that are expanded
+    ///
+    ///See [`Report::set_max_depth`](crate::Report::set_max_depth).
+    pub fn max_depth(mut self, depth: Option<usize>) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    ///Sets the order of the events in a group
+    ///
+    ///See [`Report::set_sort`](crate::Report::set_sort).
+    pub fn sort(mut self, sort: Sort) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    ///Sets the order in which reports are printed
+    ///
+    ///See [`Report::set_flush_order`](crate::Report::set_flush_order).
+    pub fn flush_order(mut self, order: Order) -> Self {
+        self.order = order;
+        self
+    }
+
+    ///Sets the destination of completed reports
+    ///
+    ///See [`Report::set_sink`](crate::Report::set_sink).
+    pub fn sink(mut self, sink: Sink) -> Self {
+        self.sink = sink;
+        self
+    }
+
+    ///Enables or disables the rendering of source locations
+    ///
+    ///See [`Report::show_locations`](crate::Report::show_locations).
+    pub fn locations(mut self, show: bool) -> Self {
+        self.locations = show;
+        self
+    }
+
+    ///Enables or disables the rendering of elapsed times
+    ///
+    ///See [`Report::show_durations`](crate::Report::show_durations).
+    pub fn durations(mut self, show: bool) -> Self {
+        self.durations = show;
+        self
+    }
+
+    ///Enables or disables the rendering of thread names
+    ///
+    ///See [`Report::show_thread`](crate::Report::show_thread).
+    pub fn thread(mut self, show: bool) -> Self {
+        self.thread = show;
+        self
+    }
+
+    ///Enables or disables the summary of errors and warnings in the header
+    ///
+    ///See [`Report::show_counts`](crate::Report::show_counts).
+    pub fn counts(mut self, show: bool) -> Self {
+        self.counts = show;
+        self
+    }
+
+    ///Sets a custom writer for the output of reports
+    ///
+    ///See [`Report::set_writer`](crate::Report::set_writer).
+    pub fn writer(mut self, writer: impl FnMut(&str) + Send + 'static) -> Self {
+        self.writer = Some(Arc::new(Mutex::new(writer)));
+        self
+    }
+
+    ///Enables or disables backtraces for errors
+    ///
+    ///See [`Report::set_backtrace`](crate::Report::set_backtrace).
+    pub fn backtrace(mut self, enabled: bool) -> Self {
+        self.backtrace = enabled;
+        self
+    }
+
+    ///Enables or disables the logging of errors that are converted into [`Error`](crate::Error)
+    ///
+    ///See [`Report::set_autolog_errors`](crate::Report::set_autolog_errors).
+    pub fn autolog_errors(mut self, enabled: bool) -> Self {
+        self.autolog = enabled;
+        self
+    }
+
+    ///Sets the sink for report digests
+    ///
+    ///See [`Report::set_digest_sink`](crate::Report::set_digest_sink).
+    pub fn digest_sink(mut self, sink: impl FnMut(&str) + Send + 'static) -> Self {
+        self.digest_sink = Some(Arc::new(Mutex::new(sink)));
+        self
+    }
+
+    ///Sets the formatter for report digests
+    ///
+    ///See [`Report::set_digest_format`](crate::Report::set_digest_format).
+    pub fn digest_format(mut self, format: impl Fn(&Digest) -> String + Send + 'static) -> Self {
+        self.digest_format = Some(Arc::new(Mutex::new(format)));
+        self
+    }
+
+    ///Sets the filter for the module paths of events
+    ///
+    ///See [`Report::set_module_filter`](crate::Report::set_module_filter).
+    pub fn module_filter(mut self, filter: impl Fn(&str) -> bool + Send + 'static) -> Self {
+        self.module_filter = Some(Box::new(filter));
+        self
+    }
+
+    pub(crate) fn get() -> MutexGuard<'static, ReportConfig> {
+        CONFIG.get_or_init(|| Mutex::new(ReportConfig::new()))
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn set(config: ReportConfig) {
+        let mut active = ReportConfig::get();
+        LEVEL.store(config.level as u8, Ordering::Relaxed);
+        #[cfg(feature = "log")]
+        crate::bridge::set_max_level(config.level);
+        BACKTRACE.store(config.backtrace, Ordering::Relaxed);
+        AUTOLOG.store(config.autolog, Ordering::Relaxed);
+        FILTERED.store(config.module_filter.is_some(), Ordering::Relaxed);
+        *active = config;
+    }
+
+    pub(crate) fn set_level(&mut self, level: Level) {
+        LEVEL.store(level as u8, Ordering::Relaxed);
+        #[cfg(feature = "log")]
+        crate::bridge::set_max_level(level);
+        self.level = level;
+    }
+
+    pub(crate) fn set_backtrace(&mut self, enabled: bool) {
+        BACKTRACE.store(enabled, Ordering::Relaxed);
+        self.backtrace = enabled;
+    }
+
+    pub(crate) fn set_autolog(&mut self, enabled: bool) {
+        AUTOLOG.store(enabled, Ordering::Relaxed);
+        self.autolog = enabled;
+    }
+
+    pub(crate) fn set_module_filter(&mut self, filter: ModuleFilter) {
+        FILTERED.store(true, Ordering::Relaxed);
+        self.module_filter = Some(filter);
+    }
+
+    pub(crate) fn get_style(&self, level: Level) -> &LevelStyle {
+        &self.styles[level as usize]
+    }
+
+    pub(crate) fn enabled(level: Level) -> bool {
+        level as u8 >= LEVEL.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn backtrace_enabled() -> bool {
+        BACKTRACE.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn autolog_enabled() -> bool {
+        AUTOLOG.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn accepts(module: &str) -> bool {
+        if !FILTERED.load(Ordering::Relaxed) { return true }
+        ReportConfig::get().module_filter.as_ref().is_none_or(|filter| filter(module))
+    }
+}
+
+pub(crate) struct LevelStyle {
+    pub(crate) label: String,
+    pub(crate) style: Style
+}
+
+impl Default for ReportConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,82 @@
+use std::fmt::Display;
+use std::panic::Location;
+use crate::{Error, Report, Result};
+
+///Adds context to errors without the [`report`](macro@crate::report) attribute
+///
+///If the value is an error, a group with the given message is opened and the error
+///is logged inside of it. The message is only formatted in this case. Otherwise,
+///nothing is logged, just like an empty group is omitted.
+///
+///Since the method is called after the expression was evaluated, events that were logged
+///while evaluating it are not part of the group. Use the [`report`](macro@crate::report)
+///attribute or [`group`](crate::group) to include them.
+///
+///# Example
+///```
+///use report::{Report, ReportExt, Result, Level};
+///
+///fn parse(input: &str) -> Result<i32> {
+///    let number = input.parse::<i32>().report(format_args!("Parsing {input:?}"))?;
+///    Ok(number)
+///}
+///
+///let capture = Report::capture(|| {
+///    assert!(parse("42").is_ok());
+///    assert!(parse("x").is_err());
+///});
+///
+///assert_eq!(capture.iter().collect::<Vec<_>>(), [
+///    (0, None, "Parsing \"x\""),
+///    (1, Some(Level::Error), "invalid digit found in string")
+///]);
+///```
+pub trait ReportExt<T> {
+    ///Logs the error inside of a group with the given message
+    fn report(self, message: impl Display) -> Result<T>;
+}
+
+impl<T, E: Display> ReportExt<T> for std::result::Result<T, E> {
+    #[track_caller]
+    fn report(self, message: impl Display) -> Result<T> {
+        let location = Location::caller();
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => {
+                let _group = Report::rec(|| message.to_string());
+                Report::error_at(location, format_args!("{error}"));
+                Err(Error)
+            }
+        }
+    }
+}
+
+///Since there is no error to log, `None` is logged as `Value is missing` inside of the group
+///
+///# Example
+///```
+///use report::{Report, ReportExt, Level};
+///
+///let capture = Report::capture(|| {
+///    assert!(std::env::args().nth(100).report("Reading the argument").is_err());
+///});
+///
+///assert_eq!(capture.iter().collect::<Vec<_>>(), [
+///    (0, None, "Reading the argument"),
+///    (1, Some(Level::Error), "Value is missing")
+///]);
+///```
+impl<T> ReportExt<T> for Option<T> {
+    #[track_caller]
+    fn report(self, message: impl Display) -> Result<T> {
+        let location = Location::caller();
+        match self {
+            Some(value) => Ok(value),
+            None => {
+                let _group = Report::rec(|| message.to_string());
+                Report::error_at(location, format_args!("Value is missing"));
+                Err(Error)
+            }
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::fmt::Write;
+use crate::{Action, Event, Level};
+
+impl Action {
+    pub(crate) fn to_html(message: &str, actions: impl IntoIterator<Item = Action>) -> String {
+        let mut output = String::from("<div class=\"report\">");
+        write!(output, "<p class=\"report-header\">{}</p>", escape(message)).ok();
+        Action::html_list(&mut output, actions);
+        output.push_str("</div>");
+        output
+    }
+
+    fn html_list(output: &mut String, actions: impl IntoIterator<Item = Action>) {
+        output.push_str("<ul>");
+        for action in actions {
+            action.html(output)
+        }
+        output.push_str("</ul>");
+    }
+
+    fn html(self, output: &mut String) {
+        match self {
+            Action::Report { message, actions, level, .. } => {
+                match level {
+                    Some(level) => write!(output, "<li class=\"report-group {}\">", level.class()).ok(),
+                    None => write!(output, "<li class=\"report-group\">").ok()
+                };
+                write!(output, "<span class=\"report-message\">{}</span>", escape(&message)).ok();
+                Action::html_list(output, actions);
+                output.push_str("</li>");
+            },
+            Action::Info(event) => event.html(output, Level::Info),
+            Action::Warn(event) => event.html(output, Level::Warn),
+            Action::Error(event) => event.html(output, Level::Error)
+        }
+    }
+}
+
+impl Level {
+    fn class(self) -> &'static str {
+        match self {
+            Level::Info => "report-info",
+            Level::Warn => "report-warn",
+            Level::Error => "report-error"
+        }
+    }
+}
+
+impl Event {
+    pub(crate) fn html(self, output: &mut String, level: Level) {
+        write!(output, "<li class=\"{}\">", level.class()).ok();
+        write!(output, "<span class=\"report-message\">{}</span>", escape(&self.message.into_string())).ok();
+        for (key, value) in self.fields {
+            write!(output, " <span class=\"report-field\">{}={}</span>", escape(key), escape(&value)).ok();
+        }
+        output.push_str("</li>");
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for char in text.chars() {
+        match char {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            char => escaped.push(char)
+        }
+    }
+    escaped
+}
@@ -0,0 +1,211 @@
+use std::future::Future;
+use std::mem::take;
+use std::panic::Location;
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use std::sync::atomic::Ordering;
+use crate::{Action, Condition, Level, Report, Root, Scope, Target, ACTIONS, SEQUENCE};
+
+///Future that carries its own report across `.await` points
+///
+///**This type should not be used directly, but through [`instrument`] or the [`log`](macro@crate::log) attribute**
+pub struct Instrumented<F: Future, T: Fn() -> String> {
+    future: Pin<Box<F>>,
+    message: T,
+    actions: Vec<Action>,
+    root: Option<Root>,
+    timing: bool,
+    condition: Condition,
+    target: Target,
+    location: &'static Location<'static>
+}
+
+///Attaches a report to a future
+///
+///Since the events of a report are stored in thread local storage, a future that
+///is moved to another thread at an `.await` point would otherwise lose its events
+///or attach them to an unrelated report. The returned future installs its own report
+///before every `poll` and stores it again afterwards, so it can be used with
+///multi-threaded executors. The report is printed once the future completes,
+///or when it is dropped after being polled at least once. If `poll` panics, the
+///report of the polling task is restored and the events of the future are discarded.
+///
+///The [`log`](macro@crate::log) attribute uses this function for `async fn`.
+///
+///# Example
+///```
+///use report::{instrument, info};
+///
+///let future = instrument(async {
+///    info!("Complementary information");
+///}, || format!("Running task"));
+///```
+#[track_caller]
+pub fn instrument<F: Future, T: Fn() -> String>(future: F, message: T) -> Instrumented<F, T> {
+    Instrumented {
+        future: Box::pin(future),
+        message,
+        actions: Vec::new(),
+        root: None,
+        timing: false,
+        condition: Condition::Always,
+        target: Target::Default,
+        location: Location::caller()
+    }
+}
+
+impl<F: Future, T: Fn() -> String> Instrumented<F, T> {
+
+    ///Renders the elapsed time in the header of the report
+    ///
+    ///See [`Report::timed`]. The time is measured from the first poll.
+    pub fn timed(mut self) -> Self {
+        self.timing = true;
+        self
+    }
+
+    ///Only prints the report if it contains at least one event
+    ///
+    ///See [`Report::on_event`].
+    pub fn on_event(mut self) -> Self {
+        self.condition = Condition::OnEvent;
+        self
+    }
+
+    ///Only prints the report if it contains at least one error
+    ///
+    ///See [`Report::on_error`].
+    pub fn on_error(mut self) -> Self {
+        self.condition = Condition::OnError;
+        self
+    }
+
+    ///Prints the report to a file
+    ///
+    ///See [`Report::log_to_file`]. The file is created when this method is called.
+    pub fn to_file(mut self, path: impl AsRef<Path>) -> Self {
+        self.target = Target::file(path.as_ref());
+        self
+    }
+
+    fn finish(&mut self) {
+        let Some(root) = self.root.take() else { return };
+        Report::finish(root, take(&mut self.actions))
+    }
+}
+
+///Future that collects its events in a nested group across `.await` points
+///
+///**This type should not be used directly, but through [`instrument_group`] or the [`report`](macro@crate::report) attribute**
+pub struct InstrumentedGroup<F: Future, T: Fn() -> String> {
+    future: Pin<Box<F>>,
+    message: T,
+    actions: Vec<Action>,
+    level: Option<Level>,
+    start: Option<Instant>
+}
+
+///Attaches a nested group to a future
+///
+///This is the asynchronous counterpart of [`group`](crate::group). A guard that is held
+///across an `.await` point would collect the events of unrelated tasks that run on the
+///same thread in the meantime. The returned future instead installs its group only while
+///it is polled, and appends the group to the report of the task that polls it once the
+///future completes. Like any other group, it is omitted if there are no events.
+///Events of a future that is dropped before it completes are discarded.
+///
+///The [`report`](macro@crate::report) attribute uses this function for annotated `.await` expressions.
+///
+///# Example
+///```
+///use report::{instrument, instrument_group, info};
+///
+///let future = instrument(async {
+///    instrument_group(async {
+///        info!("Fetching data");
+///    }, || format!("Running subtask")).await
+///}, || format!("Running task"));
+///```
+pub fn instrument_group<F: Future, T: Fn() -> String>(future: F, message: T) -> InstrumentedGroup<F, T> {
+    InstrumentedGroup {
+        future: Box::pin(future),
+        message,
+        actions: Vec::new(),
+        level: None,
+        start: None
+    }
+}
+
+impl<F: Future, T: Fn() -> String> InstrumentedGroup<F, T> {
+
+    ///Renders the header of this group with the label and style of a level
+    ///
+    ///See [`Report::level`].
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = Some(level);
+        self
+    }
+}
+
+impl<F: Future, T: Fn() -> String> Unpin for InstrumentedGroup<F, T> {}
+
+impl<F: Future, T: Fn() -> String> Future for InstrumentedGroup<F, T> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+        let start = *self.start.get_or_insert_with(Instant::now);
+        let scope = Scope::nest(take(&mut self.actions));
+        let poll = self.future.as_mut().poll(context);
+        (self.actions, _) = scope.exit();
+
+        if poll.is_ready() && !self.actions.is_empty() {
+            let action = Action::Report {
+                message: (self.message)(),
+                actions: take(&mut self.actions),
+                elapsed: start.elapsed(),
+                level: self.level
+            };
+            ACTIONS.with_borrow_mut(|actions| actions.push(action))
+        }
+
+        poll
+    }
+}
+
+impl<F: Future, T: Fn() -> String> Unpin for Instrumented<F, T> {}
+
+impl<F: Future, T: Fn() -> String> Future for Instrumented<F, T> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+        if self.root.is_none() {
+            self.root = Some(Root {
+                message: (self.message)(),
+                location: self.location,
+                start: Instant::now(),
+                timing: self.timing,
+                condition: self.condition,
+                sequence: SEQUENCE.fetch_add(1, Ordering::Relaxed),
+                target: self.target.clone()
+            })
+        }
+
+        let scope = Scope::enter(take(&mut self.actions), self.root.take());
+        let poll = self.future.as_mut().poll(context);
+        (self.actions, self.root) = scope.exit();
+
+        if poll.is_ready() {
+            self.finish()
+        }
+
+        poll
+    }
+}
+
+impl<F: Future, T: Fn() -> String> Drop for Instrumented<F, T> {
+    fn drop(&mut self) {
+        self.finish()
+    }
+}
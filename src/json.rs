@@ -0,0 +1,47 @@
+use serde_json::{json, Map, Value};
+use crate::{Action, Event, Level};
+
+impl Action {
+    pub(crate) fn to_json(message: &str, actions: impl IntoIterator<Item = Action>) -> Value {
+        json!({
+            "message": message,
+            "events": actions.into_iter().map(Action::json).collect::<Vec<_>>()
+        })
+    }
+
+    fn json(self) -> Value {
+        match self {
+            Action::Report { message, actions, level, .. } => {
+                let mut value = Action::to_json(&message, actions);
+                if let Some(level) = level {
+                    value["level"] = Value::from(level.name());
+                }
+                value
+            },
+            Action::Info(event) => event.json(Level::Info),
+            Action::Warn(event) => event.json(Level::Warn),
+            Action::Error(event) => event.json(Level::Error)
+        }
+    }
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error"
+        }
+    }
+}
+
+impl Event {
+    pub(crate) fn json(self, level: Level) -> Value {
+        let mut value = json!({ "level": level.name(), "message": self.message.into_string() });
+        if !self.fields.is_empty() {
+            let fields = self.fields.into_iter().map(|(key, value)| (key.to_string(), Value::String(value)));
+            value["fields"] = Value::Object(fields.collect::<Map<_, _>>());
+        }
+        value
+    }
+}
@@ -1,19 +1,83 @@
 #![doc = include_str!("../README.md")]
 
 use std::fmt::Arguments;
-use std::cell::Cell;
-use std::mem::take;
-use console::{pad_str, Alignment, Style, Term};
+use std::cell::{Cell, RefCell};
+use std::cmp::Reverse;
+use std::sync::atomic::{AtomicI32, AtomicU64, Ordering};
+use std::mem::{replace, take};
+use std::panic::{catch_unwind, AssertUnwindSafe, Location};
+use std::time::{Duration, Instant};
+use std::thread::current;
+use std::backtrace::{Backtrace, BacktraceStatus};
+use console::{measure_text_width, strip_ansi_codes, truncate_str, Term};
 use std::result::Result as StdResult;
 use std::error::Error as StdError;
-use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult, Write};
+use std::io::{stderr, stdout, Write as IoWrite};
+use std::fs::File;
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::collections::VecDeque;
+#[cfg(feature = "color")]
+use std::env::var_os;
 pub use report_macros::{report, log};
+#[doc(hidden)]
+pub use report_macros::event as __event;
+pub use console::Style;
+pub use builder::Builder;
+pub use capture::{Capture, Iter, StoredReport};
+pub use config::{Format, Order, Overflow, ReportConfig, Sink, Sort};
+pub use ext::ReportExt;
+pub use source::{SourceError, SourceResult};
+use config::{DigestFormat, LevelStyle, Writer};
+pub use theme::Theme;
+pub use instrument::{instrument, instrument_group, Instrumented, InstrumentedGroup};
+#[cfg(feature = "log")]
+pub use bridge::{init_log_bridge, LogBridge};
+#[cfg(feature = "signal")]
+pub use signal::install_signal_handler;
+
+#[cfg(feature = "log")]
+mod bridge;
+///Helpers for testing code that logs to reports
+#[cfg(feature = "testing")]
+pub mod testing;
+mod builder;
+mod capture;
+mod config;
+mod ext;
+mod instrument;
+#[cfg(feature = "signal")]
+mod signal;
+#[cfg(feature = "html")]
+mod html;
+#[cfg(feature = "json")]
+mod json;
+mod source;
+mod theme;
+
+#[cfg(feature = "unicode")]
+const DASH: &str = "—";
+#[cfg(not(feature = "unicode"))]
+const DASH: &str = "-";
+
+const RESET: &str = "\x1b[0m";
+const MIN_WIDTH: usize = 20;
+
+static EXIT_CODE: AtomicI32 = AtomicI32::new(0);
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+static RING: Mutex<VecDeque<StoredReport>> = Mutex::new(VecDeque::new());
 
 thread_local! {
-    static ACTIONS: Cell<Vec<Action>> = Cell::default();
+    static ACTIONS: RefCell<Vec<Action>> = RefCell::default();
     static ACTIVE: Cell<bool> = Cell::default();
+    static SPARE: Cell<Vec<Action>> = Cell::default();
+    static ROOT: Cell<Option<Root>> = Cell::default();
+    static OUTPUT: RefCell<Option<String>> = RefCell::default();
+    static QUEUE: RefCell<Vec<(u64, String)>> = RefCell::default();
 }
 
+
 ///Custom result type without error information
 /// 
 ///The error context is stored in thread local storage and is
@@ -24,7 +88,8 @@ pub type Result<T = ()> = StdResult<T, Error>;
 
 ///Custom error type without context information
 /// 
-///This type calls the [`error`](macro@error) macro in its `From` implementation.
+///This type calls the [`error`](macro@error) macro in its `From` implementation,
+///unless this was disabled with [`Report::set_autolog_errors`].
 ///The error context is then stored in thread local storage and will be printed
 ///by the `Drop` implementation of the [`Report`] type.
 pub struct Error;
@@ -36,23 +101,132 @@ pub struct Report<T: Fn() -> String> {
     message: T,
     actions: Vec<Action>,
     active: bool,
-    log: bool
+    log: bool,
+    level: Option<Level>,
+    root: Option<Root>,
+    start: Instant
+}
+
+///One-line summary of a completed top-level report
+///
+///A digest is emitted to the sink registered with [`Report::set_digest_sink`]
+///every time a report created by [`log`](macro@log) is printed.
+///By default it is formatted as `✔ Running task — 0 errors, 2 warnings`, followed by
+///the elapsed time like `(1.2s)` if [durations](Report::show_durations) are rendered.
+///The format can be changed with [`Report::set_digest_format`].
+///
+///# Example
+///```
+///use report::{Digest, Report};
+///use std::time::Duration;
+///
+///let digest = Digest {
+///    message: String::from("Running task"),
+///    errors: 0,
+///    warnings: 2,
+///    infos: 1,
+///    elapsed: Duration::from_millis(1200)
+///};
+///assert!(digest.to_string().ends_with("0 errors, 2 warnings"));
+///
+///Report::show_durations(true);
+///assert!(digest.to_string().ends_with("2 warnings (1.2s)"));
+///```
+#[derive(Debug, Clone)]
+pub struct Digest {
+    ///Message of the report
+    pub message: String,
+    ///Number of errors, including the ones in nested groups
+    pub errors: usize,
+    ///Number of warnings, including the ones in nested groups
+    pub warnings: usize,
+    ///Number of infos, including the ones in nested groups
+    pub infos: usize,
+    ///Time between the creation and the drop of the report
+    pub elapsed: Duration
+}
+
+#[derive(Default, Clone, Copy)]
+struct Counts {
+    errors: usize,
+    warnings: usize,
+    infos: usize
+}
+
+///Severity of an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    ///Event logged with [`info`](macro@info)
+    Info,
+    ///Event logged with [`warn`](macro@warn)
+    Warn,
+    ///Event logged with [`error`](macro@error)
+    Error
 }
 
 enum Action {
     Report {
         message: String,
-        actions: Vec<Action>
+        actions: Vec<Action>,
+        elapsed: Duration,
+        level: Option<Level>
     },
-    Info(String),
-    Warn(String),
-    Error(String),
+    Info(Event),
+    Warn(Event),
+    Error(Event),
+}
+
+struct Root {
+    message: String,
+    location: &'static Location<'static>,
+    start: Instant,
+    timing: bool,
+    condition: Condition,
+    sequence: u64,
+    target: Target
+}
+
+#[derive(Clone)]
+enum Target {
+    Default,
+    File(Arc<File>),
+    Stderr
+}
+
+#[derive(Clone, Copy)]
+enum Condition {
+    Always,
+    OnEvent,
+    OnError
+}
+
+type Field = (&'static str, String);
+
+struct Event {
+    message: Message,
+    fields: Vec<Field>,
+    location: Option<&'static Location<'static>>,
+    backtrace: Option<Box<Backtrace>>
+}
+
+enum Message {
+    Text(String),
+    Deferred(Box<dyn Fn() -> String + Send>)
+}
+
+struct Scope {
+    actions: Vec<Action>,
+    active: bool,
+    root: Option<Option<Root>>
 }
 
 impl Report<fn() -> String> {
 
     ///Logs a message with the `info` prefix
     ///
+    ///The location of the caller is recorded, so that it can be
+    ///rendered if [`Report::show_locations`] is enabled.
+    ///
     ///# Example
     ///```
     ///use report::Report;
@@ -60,20 +234,16 @@ impl Report<fn() -> String> {
     ///let data = 42;
     ///Report::info(format_args!("Data: {data}"));
     ///```
+    #[track_caller]
     pub fn info(message: Arguments) {
-        if !ACTIVE.get() {
-            #[cfg(feature = "color")]
-            return println!("{}: {message}", Style::new().blue().apply_to("info"));
-            #[cfg(not(feature = "color"))]
-            return println!("info: {message}");
-        }
-        let mut actions = ACTIONS.take();
-        actions.push(Action::Info(message.to_string()));
-        ACTIONS.set(actions);
+        Report::log_event(Level::Info, message)
     }
 
     ///Logs a message with the `warning` prefix
     ///
+    ///The location of the caller is recorded, so that it can be
+    ///rendered if [`Report::show_locations`] is enabled.
+    ///
     ///# Example
     ///```
     ///use report::Report;
@@ -81,20 +251,16 @@ impl Report<fn() -> String> {
     ///let data = 42;
     ///Report::warn(format_args!("Warning: {data}"));
     ///```
+    #[track_caller]
     pub fn warn(message: Arguments) {
-        if !ACTIVE.get() {
-            #[cfg(feature = "color")]
-            return println!("{}: {message}", Style::new().yellow().apply_to("warning"));
-            #[cfg(not(feature = "color"))]
-            return println!("warning: {message}");
-        }
-        let mut actions = ACTIONS.take();
-        actions.push(Action::Warn(message.to_string()));
-        ACTIONS.set(actions);
+        Report::log_event(Level::Warn, message)
     }
 
     ///Logs a message with the `error` prefix
     ///
+    ///The location of the caller is recorded, so that it can be
+    ///rendered if [`Report::show_locations`] is enabled.
+    ///
     ///# Example
     ///```
     ///use report::Report;
@@ -102,118 +268,1624 @@ impl Report<fn() -> String> {
     ///let data = 42;
     ///Report::error(format_args!("Error: {data}"));
     ///```
+    #[track_caller]
     pub fn error(message: Arguments) {
+        Report::log_event(Level::Error, message)
+    }
+
+    ///Logs a message with a level that is only known at runtime
+    ///
+    ///The location of the caller is recorded, so that it can be
+    ///rendered if [`Report::show_locations`] is enabled. This is the
+    ///function counterpart of the [`event`](macro@event) macro.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, Level};
+    ///
+    ///let strict = true;
+    ///let level = if strict { Level::Error } else { Level::Warn };
+    ///Report::log_event(level, format_args!("Missing license field"));
+    ///```
+    #[track_caller]
+    pub fn log_event(level: Level, message: Arguments) {
+        Report::event(level, Some(Location::caller()), message, &[])
+    }
+
+    ///Logs a message with the `info` prefix, which was emitted at the given location
+    ///
+    ///# Example
+    ///```
+    ///use report::Report;
+    ///use std::panic::Location;
+    ///
+    ///Report::info_at(Location::caller(), format_args!("Data: 42"));
+    ///```
+    pub fn info_at(location: &'static Location<'static>, message: Arguments) {
+        Report::event(Level::Info, Some(location), message, &[])
+    }
+
+    ///Logs a message with the `warning` prefix, which was emitted at the given location
+    ///
+    ///# Example
+    ///```
+    ///use report::Report;
+    ///use std::panic::Location;
+    ///
+    ///Report::warn_at(Location::caller(), format_args!("Warning: 42"));
+    ///```
+    pub fn warn_at(location: &'static Location<'static>, message: Arguments) {
+        Report::event(Level::Warn, Some(location), message, &[])
+    }
+
+    ///Logs a message with the `error` prefix, which was emitted at the given location
+    ///
+    ///# Example
+    ///```
+    ///use report::Report;
+    ///use std::panic::Location;
+    ///
+    ///Report::error_at(Location::caller(), format_args!("Error: 42"));
+    ///```
+    pub fn error_at(location: &'static Location<'static>, message: Arguments) {
+        Report::event(Level::Error, Some(location), message, &[])
+    }
+
+    ///Logs a message with key-value fields, which was emitted at the given location
+    ///
+    ///The fields are only formatted if the level is enabled.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, Level};
+    ///use std::panic::Location;
+    ///
+    ///let size = 42;
+    ///Report::event_at(Level::Info, Location::caller(), format_args!("Opened"), &[("size", format_args!("{size}"))]);
+    ///```
+    pub fn event_at(level: Level, location: &'static Location<'static>, message: Arguments, fields: &[(&'static str, Arguments)]) {
+        Report::event(level, Some(location), message, fields)
+    }
+
+    ///Logs a message with key-value fields, which was emitted in the given module
+    ///
+    ///This is the same as [`Report::event_at`], but the event is discarded if the
+    ///module path is rejected by the filter set with [`Report::set_module_filter`].
+    ///It is used by the [`event`](macro@event), [`info`](macro@info), [`warn`](macro@warn)
+    ///and [`error`](macro@error) macros.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, Level};
+    ///use std::panic::Location;
+    ///
+    ///Report::event_in(module_path!(), Level::Info, Location::caller(), format_args!("Opened"), &[]);
+    ///```
+    pub fn event_in(module: &str, level: Level, location: &'static Location<'static>, message: Arguments, fields: &[(&'static str, Arguments)]) {
+        if !ReportConfig::accepts(module) { return }
+        Report::event(level, Some(location), message, fields)
+    }
+
+    ///Logs a message, which is only formatted once it is rendered
+    ///
+    ///Unlike the other logging functions, the message is not formatted immediately.
+    ///Events that are never rendered therefore don't pay for their formatting.
+    ///Since the closure is stored until then, it has to own everything it uses.
+    ///The closure is called before the configuration is locked, so it may log events
+    ///itself, which are added to the enclosing report or printed immediately.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, Level};
+    ///
+    ///let data = vec![1, 2, 3];
+    ///Report::defer(Level::Info, move || format!("Data: {data:?}"));
+    ///```
+    ///
+    ///```
+    ///use report::{Report, Level, info};
+    ///use std::sync::{Arc, Mutex};
+    ///
+    ///let output = Arc::new(Mutex::new(String::new()));
+    ///let sink = output.clone();
+    ///Report::set_writer(move |text| sink.lock().unwrap().push_str(text));
+    ///
+    ///let report = Report::log(|| format!("Running task"));
+    ///Report::defer(Level::Info, || {
+    ///    info!("Formatting data");
+    ///    format!("Data: 42")
+    ///});
+    ///drop(report);
+    ///
+    ///let output = output.lock().unwrap();
+    ///assert!(output.contains("Data: 42"));
+    ///assert!(output.starts_with("info: Formatting data\n"));
+    ///```
+    #[track_caller]
+    pub fn defer(level: Level, message: impl Fn() -> String + Send + 'static) {
+        let location = Some(Location::caller());
+        #[cfg(feature = "signal")]
+        signal::check();
+        if !ReportConfig::enabled(level) { return }
+        let event = Event { message: Message::Deferred(Box::new(message)), fields: Vec::new(), location, backtrace: Event::get_backtrace(level) };
+        if !ACTIVE.get() { return Report::print_event(level, event) }
+        ACTIONS.with_borrow_mut(|actions| actions.push(Action::new(level, event)))
+    }
+
+    ///Logs many already formatted messages at once
+    ///
+    ///This has the same effect as logging every message with [`Report::info`], [`Report::warn`]
+    ///or [`Report::error`], including the filtering by [level](Report::set_level), but only
+    ///accesses the events of the active report once. All events share the location of the caller.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, Level};
+    ///
+    ///let diagnostics = vec![
+    ///    (Level::Warn, String::from("Unused variable `x`")),
+    ///    (Level::Error, String::from("Expected `;`"))
+    ///];
+    ///
+    ///let capture = Report::capture(|| Report::extend(diagnostics));
+    ///assert_eq!(capture.iter().collect::<Vec<_>>(), [
+    ///    (0, Some(Level::Warn), "Unused variable `x`"),
+    ///    (0, Some(Level::Error), "Expected `;`")
+    ///]);
+    ///```
+    ///
+    ///The iterator may log events itself, which are added before the ones it yields:
+    ///```
+    ///use report::{Report, Level, info};
+    ///
+    ///let capture = Report::capture(|| Report::extend((0..2).map(|index| {
+    ///    info!("Checking file {index}");
+    ///    (Level::Warn, format!("File {index} is empty"))
+    ///})));
+    ///assert_eq!(capture.iter().collect::<Vec<_>>(), [
+    ///    (0, Some(Level::Info), "Checking file 0"),
+    ///    (0, Some(Level::Info), "Checking file 1"),
+    ///    (0, Some(Level::Warn), "File 0 is empty"),
+    ///    (0, Some(Level::Warn), "File 1 is empty")
+    ///]);
+    ///```
+    #[track_caller]
+    pub fn extend(events: impl IntoIterator<Item = (Level, String)>) {
+        let location = Some(Location::caller());
+        #[cfg(feature = "signal")]
+        signal::check();
+        let events: Vec<_> = events.into_iter().filter(|(level, _)| ReportConfig::enabled(*level)).map(|(level, message)| {
+            (level, Event { message: Message::Text(message), fields: Vec::new(), location, backtrace: Event::get_backtrace(level) })
+        }).collect();
+
         if !ACTIVE.get() {
-            #[cfg(feature = "color")]
-            return println!("{}: {message}", Style::new().red().apply_to("error"));
-            #[cfg(not(feature = "color"))]
-            return println!("error: {message}");
+            return events.into_iter().for_each(|(level, event)| Report::print_event(level, event))
+        }
+
+        ACTIONS.with_borrow_mut(|actions| actions.extend(events.into_iter().map(|(level, event)| Action::new(level, event))))
+    }
+
+    ///Collects all nested logging events under an already formatted message
+    ///
+    ///This is the same as [`Report::rec`], except that the message is formatted
+    ///by the caller, even if the group is never rendered. It is used by
+    ///`#[report(eager, "...")]`, so that the arguments of the message are no
+    ///longer borrowed while the annotated expression is evaluated.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, info};
+    ///
+    ///let path = String::from("Cargo.toml");
+    ///let report = Report::rec_eager(format!("Opening {path}"));
+    ///drop(path);
+    ///info!("Complementary information");
+    ///drop(report);
+    ///```
+    #[track_caller]
+    pub fn rec_eager(message: String) -> Report<impl Fn() -> String> {
+        Report::rec(move || message.clone())
+    }
+
+    ///Sets the glyphs used to draw reports
+    ///
+    ///See [`Theme`] for details.
+    pub fn set_theme(theme: Theme) {
+        ReportConfig::get().theme = theme
+    }
+
+    ///Sets the width of the indentation of nested events
+    ///
+    ///Every level of nesting is indented by this many columns, which is 4 by default.
+    ///Smaller values leave more room for messages on narrow terminals. The glyphs of
+    ///the [`Theme`] are shortened or extended to match. The width is at least 2.
+    ///
+    ///# Example
+    ///```
+    ///use report::Report;
+    ///
+    ///Report::set_indent(2);
+    ///```
+    pub fn set_indent(width: usize) {
+        ReportConfig::get().indent = width
+    }
+
+    ///Sets the minimum level of events
+    ///
+    ///Events below this level are discarded before their message is formatted.
+    ///By default, all events are logged.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, Level, info, warn};
+    ///
+    ///Report::set_level(Level::Warn);
+    ///let capture = Report::capture(|| {
+    ///    info!("Discarded");
+    ///    warn!("Logged");
+    ///});
+    ///assert_eq!(capture.iter().count(), 1);
+    ///# Report::set_level(Level::Info);
+    ///```
+    pub fn set_level(level: Level) {
+        ReportConfig::get().set_level(level)
+    }
+
+    ///Sets a filter for the module paths of events
+    ///
+    ///Events logged with the macros of this crate are discarded before their message
+    ///is formatted if the filter returns `false` for the [`module_path!`] of the caller.
+    ///Records forwarded by the `log` bridge are filtered by their module path as well.
+    ///This makes it possible to silence libraries that log to reports. The filter is
+    ///called while the configuration is locked, so it must not change the configuration.
+    ///By default, all modules are accepted.
+    ///
+    ///Errors that are logged when the `?` operator converts them into [`Error`] are not
+    ///filtered, since the module path of the conversion is unknown. They can be disabled
+    ///for all modules with [`Report::set_autolog_errors`].
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, info};
+    ///
+    ///mod noisy {
+    ///    pub fn run() {
+    ///        report::info!("Discarded");
+    ///    }
+    ///}
+    ///
+    ///Report::set_module_filter(|path| !path.ends_with("::noisy"));
+    ///let capture = Report::capture(|| {
+    ///    noisy::run();
+    ///    info!("Logged");
+    ///});
+    ///assert_eq!(capture.iter().count(), 1);
+    ///```
+    pub fn set_module_filter(filter: impl Fn(&str) -> bool + Send + 'static) {
+        ReportConfig::get().set_module_filter(Box::new(filter))
+    }
+
+    ///Enables or disables backtraces for errors
+    ///
+    ///When enabled, a backtrace is captured for every error event, including the ones created
+    ///by [`bail!`] and the `?` operator, and rendered in dimmed lines below the message.
+    ///Like the backtraces of panics, they are only captured if the `RUST_LIB_BACKTRACE` or
+    ///`RUST_BACKTRACE` environment variable is set to a value other than `0`. When disabled,
+    ///which is the default, backtraces are neither captured nor formatted.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, error};
+    ///
+    ///Report::set_backtrace(true);
+    ///error!("Something went wrong");
+    ///```
+    pub fn set_backtrace(enabled: bool) {
+        ReportConfig::get().set_backtrace(enabled)
+    }
+
+    ///Enables or disables the logging of errors that are converted into [`Error`]
+    ///
+    ///By default, the `From` implementations of [`Error`] and [`SourceError`] log the
+    ///converted error, so `?` is enough to report an error. When disabled, the conversion
+    ///only creates the [`Error`] and logging is left to explicit calls of [`error!`] or
+    ///[`bail!`], for example to avoid logging the same error in every function it passes
+    ///through. [`ReportExt::report`], which adds context to an error, logs the error
+    ///regardless of this option, since it is an explicit call as well.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, Result};
+    ///
+    ///fn parse(input: &str) -> Result<i32> {
+    ///    Ok(input.parse::<i32>()?)
+    ///}
+    ///
+    ///Report::set_autolog_errors(false);
+    ///let capture = Report::capture(|| assert!(parse("x").is_err()));
+    ///assert_eq!(capture.iter().count(), 0);
+    ///```
+    pub fn set_autolog_errors(enabled: bool) {
+        ReportConfig::get().set_autolog(enabled)
+    }
+
+    ///Registers a secondary sink for report digests
+    ///
+    ///Every completed top-level report additionally emits a single [`Digest`] line
+    ///to this sink. The regular output of the report is not affected.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, warn};
+    ///use std::sync::{Arc, Mutex};
+    ///
+    ///let lines = Arc::new(Mutex::new(Vec::new()));
+    ///let sink = lines.clone();
+    ///Report::set_digest_sink(move |line| sink.lock().unwrap().push(line.to_string()));
+    ///
+    ///let report = Report::log(|| format!("Running task"));
+    ///warn!("Something looks off");
+    ///drop(report);
+    ///
+    ///assert!(lines.lock().unwrap()[0].contains("Running task"));
+    ///assert!(lines.lock().unwrap()[0].contains("0 errors, 1 warning"));
+    ///```
+    ///
+    ///The sink runs after the configuration is unlocked, so it can log events itself:
+    ///```
+    ///use report::{Report, info};
+    ///use std::sync::{Arc, Mutex};
+    ///
+    ///let output = Arc::new(Mutex::new(String::new()));
+    ///let writer = output.clone();
+    ///Report::set_writer(move |text| writer.lock().unwrap().push_str(text));
+    ///Report::set_digest_sink(|line| info!("Digest: {}", line.contains("Running task")));
+    ///
+    ///let report = Report::log(|| format!("Running task"));
+    ///info!("Complementary information");
+    ///drop(report);
+    ///
+    ///assert!(output.lock().unwrap().ends_with("info: Digest: true\n"));
+    ///```
+    pub fn set_digest_sink(sink: impl FnMut(&str) + Send + 'static) {
+        ReportConfig::get().digest_sink = Some(Arc::new(Mutex::new(sink)));
+    }
+
+    ///Writes the output of reports to a custom writer instead of stdout
+    ///
+    ///The writer receives every rendered report, including its frame, as a single string,
+    ///as well as events that are printed outside of a report. This makes it possible to
+    ///forward reports to a file, a socket or a larger document. Reports of different
+    ///threads are never interleaved, since each of them is passed to the writer at once.
+    ///The writer is called while it is locked, so it must not log events itself.
+    ///
+    ///Since the size of the terminal is meaningless for a custom writer, frames are only
+    ///drawn with a [fallback width](Report::set_fallback_width), and colors are only used
+    ///if they are [forced](Report::set_color).
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, Theme, log, info};
+    ///use std::sync::{Arc, Mutex};
+    ///use std::thread;
+    ///
+    ///let reports = Arc::new(Mutex::new(Vec::new()));
+    ///let sink = reports.clone();
+    ///Report::set_writer(move |output| sink.lock().unwrap().push(output.to_string()));
+    ///Report::set_theme(Theme::UNICODE);
+    ///
+    ///#[log("Running worker")]
+    ///fn worker() {
+    ///    info!("First event");
+    ///    info!("Second event");
+    ///}
+    ///
+    ///let threads: Vec<_> = (0..4).map(|_| thread::spawn(worker)).collect();
+    ///threads.into_iter().for_each(|thread| thread.join().unwrap());
+    ///
+    ///let reports = reports.lock().unwrap();
+    ///assert_eq!(reports.len(), 4);
+    ///for report in reports.iter() {
+    ///    assert_eq!(report, " Running worker\n ├── info: First event\n ╰── info: Second event\n");
+    ///}
+    ///```
+    pub fn set_writer(writer: impl FnMut(&str) + Send + 'static) {
+        ReportConfig::get().writer = Some(Arc::new(Mutex::new(writer)));
+    }
+
+    ///Replaces the default formatting of report digests
+    ///
+    ///# Example
+    ///```
+    ///use report::Report;
+    ///
+    ///Report::set_digest_format(|digest| format!("{}: {} errors", digest.message, digest.errors));
+    ///```
+    pub fn set_digest_format(format: impl Fn(&Digest) -> String + Send + 'static) {
+        ReportConfig::get().digest_format = Some(Arc::new(Mutex::new(format)));
+    }
+
+    ///Sets the style of the header of reports
+    ///
+    ///The style is applied to the message of every report created by [`log`](macro@log),
+    ///for example to render it in bold. By default, the header is not styled. The style
+    ///is only applied if the `color` feature is enabled.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, Style};
+    ///
+    ///Report::set_header_style(Style::new().bold());
+    ///```
+    pub fn set_header_style(style: Style) {
+        ReportConfig::get().header_style = style
+    }
+
+    ///Enables or disables the compact rendering of reports with a single event
+    ///
+    ///When enabled, a report that contains exactly one event and no groups is printed
+    ///on a single line without a frame, like `Running task: info: Done`. Reports with
+    ///more events, nested groups or messages that span multiple lines are rendered as usual.
+    ///This option is disabled by default.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, info};
+    ///use std::sync::{Arc, Mutex};
+    ///
+    ///let output = Arc::new(Mutex::new(String::new()));
+    ///let sink = output.clone();
+    ///Report::set_writer(move |text| sink.lock().unwrap().push_str(text));
+    ///Report::set_fallback_width(Some(40));
+    ///Report::set_compact(true);
+    ///
+    ///let report = Report::log(|| format!("Running task"));
+    ///info!("Done");
+    ///drop(report);
+    ///
+    ///assert_eq!(*output.lock().unwrap(), "Running task: info: Done\n");
+    ///```
+    pub fn set_compact(compact: bool) {
+        ReportConfig::get().compact = compact
+    }
+
+    ///Enables or disables the row between the header and the events of a report
+    ///
+    ///The row is only drawn if the report is framed. Without it, the events directly
+    ///follow the header inside of the same frame. This option is enabled by default.
+    ///
+    ///# Example
+    ///```
+    ///use report::Report;
+    ///
+    ///Report::show_separator(false);
+    ///```
+    pub fn show_separator(show: bool) {
+        ReportConfig::get().separator = show
+    }
+
+    ///Sets the label and style of a level
+    ///
+    ///The label and style are used for events of this level, both inside
+    ///of reports and when they are printed immediately. The style is only
+    ///applied if the `color` feature is enabled.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, Level, Style};
+    ///
+    ///Report::set_style(Level::Error, Style::new().magenta().bold(), "fehler");
+    ///```
+    pub fn set_style(level: Level, style: Style, label: &str) {
+        ReportConfig::get().styles[level as usize] = LevelStyle { label: label.to_string(), style }
+    }
+
+    ///Overrides whether colors are used
+    ///
+    ///By default, colors are used if [`console::colors_enabled`] returns `true`, which
+    ///respects `CLICOLOR` and `CLICOLOR_FORCE`, unless `NO_COLOR` is set or the output is
+    ///redirected with [`Report::set_writer`], so that it doesn't contain escape sequences.
+    ///`Some(true)` and `Some(false)` always or never use colors, while `None` restores the default.
+    ///Without the `color` feature, colors are never used.
+    ///
+    ///# Example
+    ///```
+    ///use report::Report;
+    ///
+    ///Report::set_color(Some(false));
+    ///```
+    ///
+    ///Escape sequences don't count towards the width of a row, so the frame stays aligned:
+    ///```
+    ///# #[cfg(feature = "frame")] {
+    ///use report::{Report, error};
+    ///use console::measure_text_width;
+    ///use std::sync::{Arc, Mutex};
+    ///
+    ///let output = Arc::new(Mutex::new(String::new()));
+    ///let sink = output.clone();
+    ///Report::set_writer(move |text| sink.lock().unwrap().push_str(text));
+    ///Report::set_fallback_width(Some(40));
+    ///Report::set_color(Some(true));
+    ///
+    ///let report = Report::log(|| format!("Running task"));
+    ///error!("Something went wrong");
+    ///drop(report);
+    ///
+    ///let output = output.lock().unwrap();
+    ///assert!(output.lines().all(|line| measure_text_width(line) == 40));
+    ///# }
+    ///```
+    pub fn set_color(color: Option<bool>) {
+        ReportConfig::get().color = color
+    }
+
+    ///Enables or disables icons in place of the labels of events
+    ///
+    ///When enabled, events are prefixed with `ℹ️`, `⚠️` and `❌` instead of
+    ///the labels set with [`Report::set_style`], which makes it easier to scan long reports
+    ///in terminals that render emoji. Icons are measured as two columns wide, so frames stay
+    ///aligned. This option is disabled by default.
+    ///
+    ///# Example
+    ///```
+    ///use report::Report;
+    ///
+    ///Report::set_icons(true);
+    ///```
+    pub fn set_icons(icons: bool) {
+        ReportConfig::get().icons = icons
+    }
+
+    ///Enables or disables the collapsing of repeated events
+    ///
+    ///When enabled, consecutive events with the same level and message are
+    ///rendered as a single line with a `(×N)` suffix. This applies to every
+    ///group separately. This option is disabled by default.
+    ///
+    ///# Example
+    ///```
+    ///use report::Report;
+    ///
+    ///Report::set_dedup(true);
+    ///```
+    pub fn set_dedup(dedup: bool) {
+        ReportConfig::get().dedup = dedup
+    }
+
+    ///Sets the handling of lines that are wider than the frame
+    ///
+    ///By default, such lines are truncated. With [`Overflow::Wrap`], they are
+    ///continued in the following rows, which are aligned with the start of the message.
+    ///Lines are broken at the last space that fits, or at the last character otherwise.
+    ///In both cases, styles that are active at the end of a row are reset before the
+    ///border and applied again in the next row.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, Overflow};
+    ///
+    ///Report::set_overflow(Overflow::Wrap);
+    ///```
+    ///
+    ///Styled text never leaks into the border or the following rows:
+    ///```
+    ///# #[cfg(all(feature = "frame", feature = "color"))] {
+    ///use report::{Report, Overflow, error, info};
+    ///use std::sync::{Arc, Mutex};
+    ///
+    ///let output = Arc::new(Mutex::new(String::new()));
+    ///let sink = output.clone();
+    ///Report::set_writer(move |text| sink.lock().unwrap().push_str(text));
+    ///Report::set_fallback_width(Some(30));
+    ///Report::set_color(Some(true));
+    ///
+    ///for overflow in [Overflow::Truncate, Overflow::Wrap] {
+    ///    Report::set_overflow(overflow);
+    ///    let report = Report::log(|| format!("Running task"));
+    ///    error!("\x1b[31mA long red error message that does not fit");
+    ///    info!("Next event");
+    ///    drop(report);
+    ///}
+    ///
+    ///let output = output.lock().unwrap();
+    ///for line in output.lines().filter(|line| line.contains("\x1b[31m")) {
+    ///    assert!(line.trim_end_matches(['│', '|', ' ']).ends_with("\x1b[0m"));
+    ///}
+    ///assert!(output.contains("\x1b[34minfo\x1b[0m: Next event"));
+    ///# }
+    ///```
+    pub fn set_overflow(overflow: Overflow) {
+        ReportConfig::get().overflow = overflow
+    }
+
+    ///Sets the maximum width of the frame, including its borders
+    ///
+    ///By default, the frame spans the whole width of the terminal. With a maximum,
+    ///it is only as wide as the terminal if the terminal is narrower than the maximum.
+    ///`None` removes the maximum.
+    ///
+    ///# Example
+    ///```
+    ///use report::Report;
+    ///
+    ///Report::set_max_width(Some(120));
+    ///```
+    ///
+    ///Frames are at least 20 columns wide, even if the maximum or the terminal is narrower:
+    ///```
+    ///# #[cfg(feature = "frame")] {
+    ///use report::{Report, Overflow, Builder};
+    ///use console::measure_text_width;
+    ///
+    ///Report::set_fallback_width(Some(80));
+    ///for width in 0..6 {
+    ///    Report::set_max_width(Some(width));
+    ///    for overflow in [Overflow::Truncate, Overflow::Wrap] {
+    ///        Report::set_overflow(overflow);
+    ///        let mut report = Builder::new("Running task");
+    ///        report.info("Complementary information");
+    ///        let output = report.render();
+    ///        assert!(output.lines().all(|line| measure_text_width(line) == 20));
+    ///    }
+    ///}
+    ///# }
+    ///```
+    pub fn set_max_width(width: Option<usize>) {
+        ReportConfig::get().max_width = width
+    }
+
+    ///Sets the width of the frame, including its borders, if the size of the terminal is unknown
+    ///
+    ///By default, reports are printed without a frame if stdout is not a terminal,
+    ///for example when it is redirected to a file. With a fallback width, these reports
+    ///are framed as well. The width is still limited by [`Report::set_max_width`],
+    ///but never below 20 columns.
+    ///`None` restores the default. Without the `frame` feature, frames are never drawn.
+    ///
+    ///# Example
+    ///```
+    ///use report::Report;
+    ///
+    ///Report::set_fallback_width(Some(80));
+    ///```
+    ///
+    ///Rows are padded by their display width, so wide characters keep the border aligned,
+    ///also when they are wrapped:
+    ///```
+    ///# #[cfg(feature = "frame")] {
+    ///use report::{Report, Overflow, Theme, info};
+    ///use std::sync::{Arc, Mutex};
+    ///
+    ///let output = Arc::new(Mutex::new(String::new()));
+    ///let sink = output.clone();
+    ///Report::set_writer(move |text| sink.lock().unwrap().push_str(text));
+    ///Report::set_theme(Theme::UNICODE);
+    ///Report::set_fallback_width(Some(24));
+    ///Report::set_overflow(Overflow::Wrap);
+    ///
+    ///let report = Report::log(|| format!("Running task"));
+    ///info!("日本語");
+    ///info!("日本語テスト");
+    ///drop(report);
+    ///
+    ///assert_eq!(*output.lock().unwrap(), "\
+    ///╭──────────────────────╮
+    ///│ Running task         │
+    ///├─┬────────────────────┤
+    ///│ ├── info: 日本語     │
+    ///│ ╰── info: 日本語テス │
+    ///│           ト         │
+    ///╰──────────────────────╯
+    ///");
+    ///# }
+    ///```
+    pub fn set_fallback_width(width: Option<usize>) {
+        ReportConfig::get().fallback_width = width
+    }
+
+    ///Sets the number of levels of nested groups that are expanded
+    ///
+    ///Only the given number of levels of nested groups are expanded. Groups below these
+    ///levels only render their header, followed by a single line like `… (12 nested events hidden)`,
+    ///which counts all events inside of the group. With a maximum of `0`, no group is expanded.
+    ///`None` removes the maximum, which is the default. JSON output is not affected.
+    ///
+    ///# Example
+    ///```
+    ///use report::Report;
+    ///
+    ///Report::set_max_depth(Some(4));
+    ///```
+    pub fn set_max_depth(depth: Option<usize>) {
+        ReportConfig::get().max_depth = depth
+    }
+
+    ///Sets the order in which reports are printed
+    ///
+    ///Since reports are printed when they are dropped, a report created by a nested
+    ///[`log`](macro@log) function is printed before the report that encloses it.
+    ///With [`Order::Fifo`], reports that complete while another report is active on
+    ///the same thread are queued instead. Once the outermost report completes, all
+    ///queued reports are printed in the order in which they were created. The queue
+    ///can be printed earlier with [`flush_all`]. By default, reports are printed immediately.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, Order, log, info};
+    ///use std::sync::{Arc, Mutex};
+    ///
+    ///let reports = Arc::new(Mutex::new(Vec::new()));
+    ///let sink = reports.clone();
+    ///Report::set_writer(move |output| sink.lock().unwrap().push(output.lines().next().unwrap().to_string()));
+    ///Report::set_flush_order(Order::Fifo);
+    ///
+    ///#[log("First report")]
+    ///fn first() {
+    ///    info!("Attached to the first report");
+    ///    second()
+    ///}
+    ///
+    ///#[log("Second report")]
+    ///fn second() {
+    ///    info!("Attached to the second report");
+    ///}
+    ///
+    ///first();
+    ///assert_eq!(*reports.lock().unwrap(), [" First report", " Second report"]);
+    ///```
+    pub fn set_flush_order(order: Order) {
+        ReportConfig::get().order = order
+    }
+
+    ///Sets the destination of completed reports
+    ///
+    ///With [`Sink::Ring`], reports created by [`log`](macro@log) are not rendered, but
+    ///their events are stored in a buffer that is shared by all threads, so that they can be
+    ///drawn by the application itself, for example in a terminal user interface. The stored
+    ///reports are retrieved with [`drain_roots`]. Reports that are written to a file and
+    ///reports that are not printed due to their conditions are not stored.
+    ///Digests are emitted either way. By default, reports are written to the output.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, Sink, Level, drain_roots, info};
+    ///
+    ///Report::set_sink(Sink::Ring { capacity: 2 });
+    ///for index in 0..3 {
+    ///    let _report = Report::log(move || format!("Report {index}"));
+    ///    info!("Event {index}");
+    ///}
+    ///
+    ///let roots = drain_roots();
+    ///assert_eq!(roots.len(), 2);
+    ///assert_eq!(roots[0].message, "Report 1");
+    ///assert_eq!(roots[1].events.iter().collect::<Vec<_>>(), [(0, Some(Level::Info), "Event 2")]);
+    ///assert!(drain_roots().is_empty());
+    ///```
+    pub fn set_sink(sink: Sink) {
+        ReportConfig::get().sink = sink
+    }
+
+    ///Sets the order in which the events of a group are rendered
+    ///
+    ///By default, events are rendered in the order they were logged. With
+    ///[`Sort::SeverityDesc`], errors are moved to the top of every group, followed by
+    ///warnings and infos. Events of the same level keep their order, and nested
+    ///groups keep their position.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, Sort};
+    ///
+    ///Report::set_sort(Sort::SeverityDesc);
+    ///```
+    pub fn set_sort(sort: Sort) {
+        ReportConfig::get().sort = sort
+    }
+
+    pub(crate) fn event(level: Level, location: Option<&'static Location<'static>>, message: Arguments, fields: &[(&'static str, Arguments)]) {
+        #[cfg(feature = "signal")]
+        signal::check();
+        if !ReportConfig::enabled(level) { return }
+        let fields = fields.iter().map(|(key, value)| (*key, value.to_string())).collect();
+        let event = Event { message: Message::Text(message.to_string()), fields, location, backtrace: Event::get_backtrace(level) };
+        if !ACTIVE.get() { return Report::print_event(level, event) }
+        ACTIONS.with_borrow_mut(|actions| actions.push(Action::new(level, event)))
+    }
+
+    fn print_event(level: Level, mut event: Event) {
+        event.message.resolve();
+        let config = ReportConfig::get();
+
+        #[cfg(feature = "json")]
+        if config.format == Format::Json {
+            let writer = config.writer.clone();
+            drop(config);
+            return Report::write(writer.as_ref(), &format!("{}\n", event.json(level)))
+        }
+
+        #[cfg(feature = "html")]
+        if config.format == Format::Html {
+            let writer = config.writer.clone();
+            drop(config);
+            let mut output = String::new();
+            event.html(&mut output, level);
+            output.push('\n');
+            return Report::write(writer.as_ref(), &output)
+        }
+
+        let message = format!("{}{}", event.message.as_str(), Action::get_fields(&event.fields));
+        let backtrace = event.backtrace.map(|backtrace| backtrace.to_string());
+        let label = Action::get_label(&config, level);
+        let mut output = format!("{label}{message}{}\n", Action::get_location(&config, event.location));
+        for line in backtrace.iter().flat_map(|backtrace| backtrace.lines()) {
+            writeln!(output, "{}", Action::get_dimmed(&config, line)).ok();
+        }
+        let writer = config.writer.clone();
+        drop(config);
+        Report::write(writer.as_ref(), &output)
+    }
+
+    fn finish(root: Root, mut actions: Vec<Action>) {
+        let Root { message, location, start, timing, condition, sequence, target } = root;
+        let elapsed = start.elapsed();
+        let counts = Counts::of(&actions);
+        EXIT_CODE.store(counts.exit_code(), Ordering::Relaxed);
+        let visible = match condition {
+            Condition::Always => true,
+            Condition::OnEvent => !actions.is_empty(),
+            Condition::OnError => counts.errors > 0
+        };
+
+        if visible {
+            actions.iter_mut().for_each(Action::resolve)
+        }
+
+        let config = ReportConfig::get();
+        let duration = (timing || config.durations).then_some(elapsed);
+
+        let writer = config.writer.clone();
+        let sink = config.digest_sink.clone();
+        let format = config.digest_format.clone();
+
+        if let (Target::Default, Sink::Ring { capacity }) = (&target, config.sink) {
+            drop(config);
+            if visible {
+                Report::store(capacity, message.clone(), elapsed, actions);
+            }
+            return Report::digest(sink, format, message, counts, elapsed)
+        }
+
+        let detached = matches!(target, Target::File(_) | Target::Stderr).then(|| OUTPUT.replace(Some(String::new())));
+        let output = match visible {
+            true => Some(Report::render(&config, &message, location, duration, counts, &mut actions)),
+            false => { actions.clear(); None }
+        };
+        if let Some(output) = detached {
+            OUTPUT.set(output)
+        }
+
+        let order = config.order;
+        drop(config);
+
+        match (target, order) {
+            (Target::File(file), _) => if let Some(output) = output {
+                let output = strip_ansi_codes(&output);
+                if let Err(error) = (&*file).write_all(output.as_bytes()) {
+                    Report::write_stderr(&format!("warning: Failed to write report: {error}"), &output)
+                }
+            },
+            (Target::Stderr, _) => if let Some(output) = output {
+                stderr().lock().write_all(output.as_bytes()).ok();
+            },
+            (Target::Default, Order::Lifo) => if let Some(output) = output {
+                Report::write(writer.as_ref(), &output)
+            },
+            (Target::Default, Order::Fifo) => {
+                QUEUE.with_borrow_mut(|queue| queue.extend(output.map(|output| (sequence, output))));
+                if !Report::is_nested() {
+                    Report::write_queue(writer.as_ref())
+                }
+            }
+        }
+
+        Report::digest(sink, format, message, counts, elapsed);
+        SPARE.set(actions)
+    }
+
+    fn store(capacity: usize, message: String, elapsed: Duration, mut actions: Vec<Action>) {
+        actions.iter_mut().for_each(Action::resolve);
+        let mut ring = RING.lock().unwrap_or_else(PoisonError::into_inner);
+        ring.push_back(StoredReport { message, elapsed, events: Capture { actions, elapsed } });
+        while ring.len() > capacity {
+            ring.pop_front();
+        }
+    }
+
+    fn digest(sink: Option<Writer>, format: Option<DigestFormat>, message: String, counts: Counts, elapsed: Duration) {
+        let Some(sink) = sink else { return };
+
+        let digest = Digest {
+            message,
+            errors: counts.errors,
+            warnings: counts.warnings,
+            infos: counts.infos,
+            elapsed
+        };
+
+        let line = match format {
+            Some(format) => (*format.lock().unwrap_or_else(PoisonError::into_inner))(&digest),
+            None => digest.to_string()
+        };
+
+        (*sink.lock().unwrap_or_else(PoisonError::into_inner))(line.as_str());
+    }
+
+    ///Enables or disables the rendering of source locations
+    ///
+    ///When enabled, the header of every report created by [`log`](macro@log)
+    ///and every event is followed by the file and line it was emitted from,
+    ///for example `src/main.rs:42`. This option is disabled by default.
+    ///
+    ///# Example
+    ///```
+    ///use report::Report;
+    ///
+    ///Report::show_locations(true);
+    ///```
+    pub fn show_locations(show: bool) {
+        ReportConfig::get().locations = show
+    }
+
+    ///Enables or disables the summary of errors and warnings in the header
+    ///
+    ///When enabled, the header of every report created by [`log`](macro@log) is followed
+    ///by the number of errors and warnings it contains, including the ones in nested groups,
+    ///for example `Running experiments — 0 errors, 2 warnings`. This option is disabled by default.
+    ///
+    ///# Example
+    ///```
+    ///use report::Report;
+    ///
+    ///Report::show_counts(true);
+    ///```
+    pub fn show_counts(show: bool) {
+        ReportConfig::get().counts = show
+    }
+
+    ///Enables or disables the rendering of thread names
+    ///
+    ///When enabled, the header of every report created by [`log`](macro@log) is followed
+    ///by the name of the thread it was printed on, for example `Running task [worker-1]`.
+    ///Threads without a name are rendered with their id instead. This helps to tell
+    ///the reports of concurrent threads apart. This option is disabled by default.
+    ///
+    ///# Example
+    ///```
+    ///use report::Report;
+    ///
+    ///Report::show_thread(true);
+    ///```
+    pub fn show_thread(show: bool) {
+        ReportConfig::get().thread = show
+    }
+
+    ///Enables or disables the rendering of elapsed times
+    ///
+    ///When enabled, the header of every report and group is followed by the time
+    ///between its creation and its drop, for example `Running task (1.3s)`.
+    ///Individual reports can be timed with [`Report::timed`] or `#[log(timing, "...")]`
+    ///instead. This option is disabled by default.
+    ///
+    ///# Example
+    ///```
+    ///use report::Report;
+    ///
+    ///Report::show_durations(true);
+    ///```
+    pub fn show_durations(show: bool) {
+        ReportConfig::get().durations = show
+    }
+
+    ///Replaces the active configuration
+    ///
+    ///All options are applied at once. See [`ReportConfig`] for details.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, ReportConfig};
+    ///
+    ///Report::configure(ReportConfig::default().locations(true));
+    ///```
+    pub fn configure(config: ReportConfig) {
+        ReportConfig::set(config)
+    }
+
+    ///Sets the output format of reports
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, Format};
+    ///
+    ///Report::set_format(Format::Human);
+    ///```
+    ///
+    ///With `Format::Json`, events outside of a report are printed as JSON as well:
+    ///```
+    ///# #[cfg(feature = "json")] {
+    ///use report::{Report, Format, info};
+    ///use std::sync::{Arc, Mutex};
+    ///
+    ///let output = Arc::new(Mutex::new(String::new()));
+    ///let writer = output.clone();
+    ///Report::set_writer(move |text| writer.lock().unwrap().push_str(text));
+    ///Report::set_format(Format::Json);
+    ///
+    ///info!("Opened file"; path = "Cargo.toml");
+    ///assert_eq!(*output.lock().unwrap(), concat!(
+    ///    r#"{"fields":{"path":"Cargo.toml"},"level":"info","message":"Opened file"}"#, "\n"
+    ///));
+    ///# }
+    ///```
+    ///
+    ///With `Format::Html`, they are printed as escaped list items:
+    ///```
+    ///# #[cfg(feature = "html")] {
+    ///use report::{Report, Format, info};
+    ///use std::sync::{Arc, Mutex};
+    ///
+    ///let output = Arc::new(Mutex::new(String::new()));
+    ///let writer = output.clone();
+    ///Report::set_writer(move |text| writer.lock().unwrap().push_str(text));
+    ///Report::set_color(Some(true));
+    ///Report::set_format(Format::Html);
+    ///
+    ///info!("<script>alert(1)</script>");
+    ///assert_eq!(*output.lock().unwrap(), concat!(
+    ///    r#"<li class="report-info"><span class="report-message">"#,
+    ///    "&lt;script&gt;alert(1)&lt;/script&gt;</span></li>\n"
+    ///));
+    ///# }
+    ///```
+    pub fn set_format(format: Format) {
+        ReportConfig::get().format = format
+    }
+
+    fn render(config: &ReportConfig, message: &str, location: &Location, elapsed: Option<Duration>, counts: Counts, actions: &mut Vec<Action>) -> String {
+        if config.dedup {
+            Action::dedup(actions)
+        }
+
+        if config.sort == Sort::SeverityDesc {
+            Action::sort(actions)
+        }
+
+        #[cfg(feature = "json")]
+        if config.format == Format::Json {
+            return format!("{}\n", Action::to_json(message, actions.drain(..)));
+        }
+
+        #[cfg(feature = "html")]
+        if config.format == Format::Html {
+            return format!("{}\n", Action::to_html(message, actions.drain(..)));
+        }
+
+        let mut output = String::new();
+        let mut prefix = String::from(" ");
+        let width = Report::get_width(config);
+
+        let counts = match config.counts {
+            true => format!(" {DASH} {counts}"),
+            false => String::new()
+        };
+        let suffix = format!("{}{counts}{}{}", Action::get_thread(config), Action::get_duration(elapsed), Action::get_location(config, Some(location)));
+        let message = Action::get_header(config, message);
+
+        if config.compact {
+            if let Some(line) = Action::compact(config, &message, &suffix, actions) {
+                return line
+            }
+        }
+
+        Action::open_frame(&mut output, config, width);
+        Action::add_lines(&mut output, config, width, " ", " ", &message, &suffix);
+
+        if !actions.is_empty() {
+            if config.separator {
+                Action::seperator(&mut output, config, width);
+            }
+            let max = actions.len().saturating_sub(1);
+            for (index, action) in actions.drain(..).enumerate() {
+                action.print(&mut output, config, &mut prefix, width, index == max, 0)
+            }
+        }
+
+        Action::close_frame(&mut output, config, width);
+        output
+    }
+
+    fn is_nested() -> bool {
+        let root = ROOT.take();
+        let nested = root.is_some();
+        ROOT.set(root);
+        nested
+    }
+
+    fn write_queue(writer: Option<&Writer>) {
+        let mut queue = QUEUE.take();
+        queue.sort_by_key(|(sequence, _)| *sequence);
+        for (_, output) in queue {
+            Report::write(writer, &output)
+        }
+    }
+
+    fn write(writer: Option<&Writer>, output: &str) {
+        let captured = OUTPUT.with_borrow_mut(|buffer| buffer.as_mut().map(|buffer| buffer.push_str(output)));
+        if captured.is_some() { return }
+        match writer {
+            Some(writer) => (*writer.lock().unwrap_or_else(PoisonError::into_inner))(output),
+            None => { stdout().lock().write_all(output.as_bytes()).ok(); }
+        }
+    }
+
+    fn write_stderr(warning: &str, output: &str) {
+        let mut stderr = stderr().lock();
+        writeln!(stderr, "{warning}").ok();
+        stderr.write_all(output.as_bytes()).ok();
+    }
+
+    fn is_redirected(config: &ReportConfig) -> bool {
+        OUTPUT.with_borrow(Option::is_some) || config.writer.is_some()
+    }
+
+    #[cfg(feature = "color")]
+    fn is_plain(config: &ReportConfig) -> bool {
+        Report::is_redirected(config) || var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+    }
+
+    fn get_width(config: &ReportConfig) -> Option<usize> {
+        let size = Term::stdout().size_checked().filter(|_| !Report::is_redirected(config));
+        let width = match size {
+            Some((_, width)) => (width as usize).saturating_sub(2),
+            None => config.fallback_width?
+        };
+        let width = match config.max_width {
+            Some(max) => width.min(max),
+            None => width
+        };
+        Some(width.max(MIN_WIDTH) - 2).filter(|_| cfg!(feature = "frame"))
+    }
+}
+
+impl Counts {
+    fn of(actions: &[Action]) -> Self {
+        let mut counts = Counts::default();
+        for action in actions {
+            match action {
+                Action::Report { actions, .. } => {
+                    let nested = Counts::of(actions);
+                    counts.errors += nested.errors;
+                    counts.warnings += nested.warnings;
+                    counts.infos += nested.infos;
+                },
+                Action::Info(..) => counts.infos += 1,
+                Action::Warn(..) => counts.warnings += 1,
+                Action::Error(..) => counts.errors += 1
+            }
+        }
+        counts
+    }
+
+    fn exit_code(&self) -> i32 {
+        match self {
+            Counts { errors: 1.., .. } => 2,
+            Counts { warnings: 1.., .. } => 1,
+            _ => 0
+        }
+    }
+}
+
+impl Scope {
+    fn enter(actions: Vec<Action>, root: Option<Root>) -> Self {
+        Scope {
+            actions: ACTIONS.replace(actions),
+            active: ACTIVE.replace(true),
+            root: Some(ROOT.replace(root))
+        }
+    }
+
+    fn nest(actions: Vec<Action>) -> Self {
+        Scope {
+            actions: ACTIONS.replace(actions),
+            active: ACTIVE.get(),
+            root: None
+        }
+    }
+
+    fn exit(self) -> (Vec<Action>, Option<Root>) {
+        let root = self.root.as_ref().and_then(|_| ROOT.take());
+        (ACTIONS.take(), root)
+    }
+}
+
+impl Target {
+    fn file(path: &Path) -> Self {
+        match File::create(path) {
+            Ok(file) => Target::File(Arc::new(file)),
+            Err(error) => {
+                eprintln!("warning: Failed to create {}: {error}", path.display());
+                Target::Stderr
+            }
+        }
+    }
+}
+
+impl Event {
+    fn get_backtrace(level: Level) -> Option<Box<Backtrace>> {
+        if level != Level::Error || !ReportConfig::backtrace_enabled() { return None }
+        let backtrace = Backtrace::capture();
+        (backtrace.status() == BacktraceStatus::Captured).then(|| Box::new(backtrace))
+    }
+}
+
+impl Message {
+    fn into_string(self) -> String {
+        match self {
+            Message::Text(text) => text,
+            Message::Deferred(message) => message()
+        }
+    }
+
+    fn resolve(&mut self) {
+        if let Message::Deferred(message) = self {
+            *self = Message::Text(message())
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        match self {
+            Message::Text(text) => text,
+            Message::Deferred(..) => ""
+        }
+    }
+}
+
+impl Action {
+    fn new(level: Level, event: Event) -> Self {
+        match level {
+            Level::Info => Action::Info(event),
+            Level::Warn => Action::Warn(event),
+            Level::Error => Action::Error(event)
+        }
+    }
+
+    fn resolve(&mut self) {
+        match self {
+            Action::Report { actions, .. } => actions.iter_mut().for_each(Action::resolve),
+            Action::Info(event) | Action::Warn(event) | Action::Error(event) => event.message.resolve()
+        }
+    }
+
+    fn get_event(&self) -> Option<(Level, &str, &[Field])> {
+        match self {
+            Action::Report { .. } => None,
+            Action::Info(event) => Some((Level::Info, event.message.as_str(), &event.fields)),
+            Action::Warn(event) => Some((Level::Warn, event.message.as_str(), &event.fields)),
+            Action::Error(event) => Some((Level::Error, event.message.as_str(), &event.fields))
+        }
+    }
+
+    fn dedup(actions: &mut Vec<Action>) {
+        let mut deduped: Vec<Action> = Vec::with_capacity(actions.len());
+        let mut count = 1;
+
+        for mut action in actions.drain(..) {
+            action.resolve();
+            if let Action::Report { actions, .. } = &mut action {
+                Action::dedup(actions)
+            }
+
+            let last = deduped.last().and_then(Action::get_event);
+            if last.is_some() && last == action.get_event() {
+                count += 1;
+                continue
+            }
+
+            Action::add_count(deduped.last_mut(), count);
+            count = 1;
+            deduped.push(action)
+        }
+
+        Action::add_count(deduped.last_mut(), count);
+        *actions = deduped
+    }
+
+    fn sort(actions: &mut Vec<Action>) {
+        let mut events = Vec::new();
+        for (index, action) in actions.iter_mut().enumerate() {
+            match action {
+                Action::Report { actions, .. } => Action::sort(actions),
+                action => events.extend(action.get_event().map(|(level, ..)| (level, index)))
+            }
+        }
+
+        events.sort_by_key(|&(level, _)| Reverse(level));
+        let mut events = events.into_iter().map(|(_, index)| index);
+        let mut slots: Vec<Option<Action>> = actions.drain(..).map(Some).collect();
+        let order: Vec<usize> = slots.iter()
+            .enumerate()
+            .map(|(index, slot)| match slot {
+                Some(Action::Report { .. }) => index,
+                _ => events.next().unwrap_or(index)
+            })
+            .collect();
+
+        actions.extend(order.into_iter().filter_map(|index| slots[index].take()))
+    }
+
+    fn add_count(action: Option<&mut Action>, count: usize) {
+        if count < 2 { return }
+        let Some(Action::Info(event) | Action::Warn(event) | Action::Error(event)) = action else { return };
+        let Message::Text(text) = &mut event.message else { return };
+        #[cfg(feature = "unicode")]
+        text.push_str(&format!(" (×{count})"));
+        #[cfg(not(feature = "unicode"))]
+        text.push_str(&format!(" (x{count})"));
+    }
+
+    fn print(self, output: &mut String, config: &ReportConfig, prefix: &mut String, width: Option<usize>, last: bool, depth: usize) {
+        match self {
+            Action::Info(event) => Action::add_event(output, config, prefix, width, last, Level::Info, event),
+            Action::Warn(event) => Action::add_event(output, config, prefix, width, last, Level::Warn, event),
+            Action::Error(event) => Action::add_event(output, config, prefix, width, last, Level::Error, event),
+            Action::Report { message, actions, elapsed, level } => {
+                let connection = config.theme.get_connection(last, config.indent);
+                let indent = config.theme.get_indent(last, config.indent);
+                let label = level.map(|level| Action::get_label(config, level)).unwrap_or_default();
+                let padding = " ".repeat(measure_text_width(&label));
+                let duration = Action::get_duration(Some(elapsed).filter(|_| config.durations));
+                Action::add_lines(output, config, width, &format!("{prefix}{connection}{label}"), &format!("{prefix}{indent}{padding}"), &message, &duration);
+                let length = prefix.len();
+                prefix.push_str(&indent);
+                if config.max_depth.is_some_and(|max| depth >= max) {
+                    Action::add_hidden(output, config, prefix, width, Counts::of(&actions))
+                } else {
+                    let max = actions.len().saturating_sub(1);
+                    for (index, action) in actions.into_iter().enumerate() {
+                        action.print(output, config, prefix, width, index == max, depth + 1)
+                    }
+                }
+                prefix.truncate(length)
+            }
+        }
+    }
+
+    fn compact(config: &ReportConfig, header: &str, suffix: &str, actions: &mut Vec<Action>) -> Option<String> {
+        let [action] = actions.as_mut_slice() else { return None };
+        let (level, event) = match action {
+            Action::Info(event) => (Level::Info, event),
+            Action::Warn(event) => (Level::Warn, event),
+            Action::Error(event) => (Level::Error, event),
+            Action::Report { .. } => return None
+        };
+
+        event.message.resolve();
+        let message = event.message.as_str();
+        if header.contains('\n') || message.contains('\n') || event.backtrace.is_some() { return None }
+
+        let label = Action::get_label(config, level);
+        let fields = Action::get_fields(&event.fields);
+        let location = Action::get_location(config, event.location);
+        let line = format!("{header}: {label}{message}{fields}{location}{suffix}\n");
+        actions.clear();
+        Some(line)
+    }
+
+    fn add_hidden(output: &mut String, config: &ReportConfig, prefix: &str, width: Option<usize>, counts: Counts) {
+        let connection = config.theme.get_connection(true, config.indent);
+        let count = counts.errors + counts.warnings + counts.infos;
+        let events = if count == 1 { "event" } else { "events" };
+        #[cfg(feature = "unicode")]
+        let message = format!("… ({count} nested {events} hidden)");
+        #[cfg(not(feature = "unicode"))]
+        let message = format!("... ({count} nested {events} hidden)");
+        Action::add_frame(output, config, width, format!("{prefix}{connection}{message}"))
+    }
+
+    fn add_event(output: &mut String, config: &ReportConfig, prefix: &str, width: Option<usize>, last: bool, level: Level, event: Event) {
+        let connection = config.theme.get_connection(last, config.indent);
+        let indent = config.theme.get_indent(last, config.indent);
+        let label = Action::get_label(config, level);
+        let padding = " ".repeat(measure_text_width(&label));
+        let suffix = format!("{}{}", Action::get_fields(&event.fields), Action::get_location(config, event.location));
+        let message = event.message.into_string();
+        let rest = format!("{prefix}{indent}{padding}");
+        Action::add_lines(output, config, width, &format!("{prefix}{connection}{label}"), &rest, &message, &suffix);
+        let Some(backtrace) = event.backtrace else { return };
+        for line in backtrace.to_string().lines() {
+            Action::add_line(output, config, width, &rest, &rest, &Action::get_dimmed(config, line))
+        }
+    }
+
+    fn add_lines(output: &mut String, config: &ReportConfig, width: Option<usize>, first: &str, rest: &str, message: &str, suffix: &str) {
+        let mut lines = message.lines();
+        let line = lines.next().unwrap_or_default();
+        match suffix.is_empty() {
+            true => Action::add_line(output, config, width, first, rest, line),
+            false => Action::add_line(output, config, width, first, rest, &format!("{line}{suffix}"))
+        }
+        for line in lines {
+            Action::add_line(output, config, width, rest, rest, line)
         }
-        let mut actions = ACTIONS.take();
-        actions.push(Action::Error(message.to_string()));
-        ACTIONS.set(actions);
     }
 
-    fn print(message: String, actions: Vec<Action>) {
-        let mut prefix = String::from(" ");
-        let width = Term::stdout()
-            .size_checked()
-            .map(|(_, width)| width as usize)
-            .map(|width| width.saturating_sub(4))
-            .filter(|_| cfg!(feature = "frame"));
-
-        Action::open_frame(width);
-        Action::add_frame(width, format!(" {message}"));
+    fn add_line(output: &mut String, config: &ReportConfig, width: Option<usize>, first: &str, rest: &str, mut line: &str) {
+        let (Some(width), Overflow::Wrap) = (width, config.overflow) else {
+            return Action::add_frame(output, config, width, format!("{first}{line}"))
+        };
 
-        if !actions.is_empty() {
-            Action::seperator(width);
-            let max = actions.len().saturating_sub(1);
-            for (index, action) in actions.into_iter().enumerate() {
-                action.print(&mut prefix, width, index == max)
-            }
+        let mut prefix = first;
+        let mut styles = String::new();
+        loop {
+            let available = width.saturating_sub(measure_text_width(prefix));
+            let (row, remainder) = Action::split_line(line, available);
+            Action::add_frame(output, config, Some(width), format!("{prefix}{styles}{row}"));
+            Action::add_styles(&mut styles, row);
+            if remainder.is_empty() { return }
+            line = remainder;
+            prefix = rest;
         }
-
-        Action::close_frame(width);
     }
-}
 
-impl Action {
-    fn print(self, prefix: &mut String, width: Option<usize>, last: bool) {
-        let connection = Action::get_connection(last);
-        match self {
-            #[cfg(not(feature = "color"))] Action::Info(message)
-                => Action::add_frame(width, format!("{prefix}{connection}info: {message}")),
-            #[cfg(not(feature = "color"))] Action::Warn(message)
-                => Action::add_frame(width, format!("{prefix}{connection}warning: {message}")),
-            #[cfg(not(feature = "color"))] Action::Error(message)
-                => Action::add_frame(width, format!("{prefix}{connection}error: {message}")),
-            #[cfg(feature = "color")] Action::Info(message)
-                => Action::add_frame(width, format!("{prefix}{connection}{}: {message}", Style::new().blue().apply_to("info"))),
-            #[cfg(feature = "color")] Action::Warn(message)
-                => Action::add_frame(width, format!("{prefix}{connection}{}: {message}", Style::new().yellow().apply_to("warning"))),
-            #[cfg(feature = "color")] Action::Error(message)
-                => Action::add_frame(width, format!("{prefix}{connection}{}: {message}", Style::new().red().apply_to("error"))),
-            Action::Report { message, actions } => {
-                Action::add_frame(width, format!("{prefix}{connection}{message}"));
-                prefix.push_str(Action::get_indent(last));
-                let max = actions.len().saturating_sub(1);
-                for (index, action) in actions.into_iter().enumerate() {
-                    action.print(prefix, width, index == max)
-                }
-                prefix.char_indices()
-                    .rev()
-                    .nth(3)
-                    .map(|(index, _)| prefix.truncate(index));
+    fn split_line(line: &str, width: usize) -> (&str, &str) {
+        let mut chars = line.char_indices();
+        let mut used = 0;
+        let mut space = None;
+
+        while let Some((index, char)) = chars.next() {
+            if char == '\x1b' {
+                chars.next();
+                chars.find(|(_, char)| ('@'..='~').contains(char));
+                continue
+            }
+
+            used += measure_text_width(char.encode_utf8(&mut [0; 4]));
+            if used <= width {
+                if char == ' ' { space = Some(index) }
+                continue
             }
+
+            let end = match space {
+                Some(space) if space > 0 => space,
+                _ if index == 0 => char.len_utf8(),
+                _ => index
+            };
+
+            return (&line[..end], line[end..].trim_start())
         }
+
+        (line, "")
     }
 
-    fn open_frame(width: Option<usize>) {
+    fn open_frame(output: &mut String, config: &ReportConfig, width: Option<usize>) {
         let Some(width) = width else { return };
-        #[cfg(feature = "unicode")]
-        println!("╭{}╮", "─".repeat(width));
-        #[cfg(not(feature = "unicode"))]
-        println!("+{}+", "-".repeat(width));
+        let Theme { top_left, horizontal, top_right, .. } = config.theme;
+        writeln!(output, "{top_left}{}{top_right}", horizontal.repeat(width)).ok();
     }
     
-    fn close_frame(width: Option<usize>) {
+    fn close_frame(output: &mut String, config: &ReportConfig, width: Option<usize>) {
         let Some(width) = width else { return };
-        #[cfg(feature = "unicode")]
-        println!("╰{}╯", "─".repeat(width));
-        #[cfg(not(feature = "unicode"))]
-        println!("+{}+", "-".repeat(width));
+        let Theme { bottom_left, horizontal, bottom_right, .. } = config.theme;
+        writeln!(output, "{bottom_left}{}{bottom_right}", horizontal.repeat(width)).ok();
     }
     
-    fn seperator(width: Option<usize>) {
+    fn seperator(output: &mut String, config: &ReportConfig, width: Option<usize>) {
         let Some(width) = width else { return };
-        #[cfg(feature = "unicode")]
-        println!("├─┬{}┤", "─".repeat(width.saturating_sub(2)));
-        #[cfg(not(feature = "unicode"))]
-        println!("+{}+", "-".repeat(width));
+        let Theme { separator_left, horizontal, separator_junction, separator_right, .. } = config.theme;
+        writeln!(output, "{separator_left}{horizontal}{separator_junction}{}{separator_right}", horizontal.repeat(width.saturating_sub(2))).ok();
     }
     
-    fn add_frame(width: Option<usize>, data: String) {
-        let Some(width) = width else { return println!("{data}") };
-        #[cfg(feature = "unicode")]
-        let vertical = "│";
-        #[cfg(not(feature = "unicode"))]
-        let vertical = "|";
-        let padded = pad_str(data.as_str(), width, Alignment::Left, Some("..."));
-        println!("{vertical}{padded}{vertical}");
+    fn add_styles(styles: &mut String, text: &str) {
+        let mut rest = text;
+        while let Some(start) = rest.find("\x1b[") {
+            rest = &rest[start + 2..];
+            let Some(end) = rest.find(|char| ('@'..='~').contains(&char)) else { return };
+            let (parameters, remainder) = rest.split_at(end);
+            rest = &remainder[1..];
+            if !remainder.starts_with('m') { continue }
+            match parameters {
+                "" | "0" => styles.clear(),
+                _ => write!(styles, "\x1b[{parameters}m").unwrap_or_default()
+            }
+        }
     }
-    
-    fn get_connection(last: bool) -> &'static str {
-        #[cfg(feature = "unicode")]
-        if last { "╰── " } else { "├── " }
-        #[cfg(not(feature = "unicode"))]
-        if last { "\\-- " } else { "|-- " }
+
+    fn add_frame(output: &mut String, config: &ReportConfig, width: Option<usize>, data: String) {
+        let Some(width) = width else {
+            writeln!(output, "{data}").ok();
+            return
+        };
+        let vertical = config.theme.vertical;
+        let mut data = match measure_text_width(&data) > width {
+            true => truncate_str(data.as_str(), width, "...").into_owned(),
+            false => data
+        };
+        let mut styles = String::new();
+        Action::add_styles(&mut styles, &data);
+        if !styles.is_empty() {
+            data.push_str(RESET)
+        }
+        let padding = " ".repeat(width.saturating_sub(measure_text_width(&data)));
+        writeln!(output, "{vertical}{data}{padding}{vertical}").ok();
     }
     
-    fn get_indent(last: bool) -> &'static str {
-        #[cfg(feature = "unicode")]
-        if last { "    " } else { "│   " }
-        #[cfg(not(feature = "unicode"))]
-        if last { "    " } else { "|   " }
+    fn get_label(config: &ReportConfig, level: Level) -> String {
+        if config.icons {
+            return format!("{} ", ["ℹ️", "⚠️", "❌"][level as usize])
+        }
+        let LevelStyle { label, style } = config.get_style(level);
+        #[cfg(feature = "color")]
+        return format!("{}: ", Action::get_style(config, style).apply_to(label));
+        #[cfg(not(feature = "color"))]
+        return { let _ = style; format!("{label}: ") };
+    }
+
+    #[cfg(feature = "color")]
+    fn get_style(config: &ReportConfig, style: &Style) -> Style {
+        match config.color {
+            Some(color) => style.clone().force_styling(color),
+            None if Report::is_plain(config) => style.clone().force_styling(false),
+            None => style.clone()
+        }
+    }
+
+    fn get_header(config: &ReportConfig, message: &str) -> String {
+        #[cfg(feature = "color")]
+        return {
+            let style = Action::get_style(config, &config.header_style);
+            let mut header = String::with_capacity(message.len());
+            for (index, line) in message.lines().enumerate() {
+                if index > 0 { header.push('\n') }
+                write!(header, "{}", style.apply_to(line)).ok();
+            }
+            header
+        };
+        #[cfg(not(feature = "color"))]
+        return { let _ = config; message.to_string() };
+    }
+
+    fn get_duration(elapsed: Option<Duration>) -> String {
+        match elapsed {
+            Some(elapsed) if elapsed.as_secs() == 0 => format!(" ({}ms)", elapsed.as_millis()),
+            Some(elapsed) => format!(" ({:.1}s)", elapsed.as_secs_f64()),
+            None => String::new()
+        }
+    }
+
+    fn get_thread(config: &ReportConfig) -> String {
+        if !config.thread { return String::new() }
+        let thread = current();
+        match thread.name() {
+            Some(name) => format!(" [{name}]"),
+            None => format!(" [{:?}]", thread.id())
+        }
+    }
+
+    fn get_fields(fields: &[(&'static str, impl Display)]) -> String {
+        fields.iter().map(|(key, value)| format!(" {key}={value}")).collect()
+    }
+
+    fn get_location(config: &ReportConfig, location: Option<&Location>) -> String {
+        let Some(location) = location.filter(|_| config.locations) else { return String::new() };
+        format!(" {}", Action::get_dimmed(config, &format!("{}:{}", location.file(), location.line())))
+    }
+
+    fn get_dimmed(config: &ReportConfig, text: &str) -> String {
+        #[cfg(feature = "color")]
+        return Action::get_style(config, &Style::new().dim()).apply_to(text).to_string();
+        #[cfg(not(feature = "color"))]
+        return { let _ = config; text.to_string() };
     }
 }
 
@@ -222,6 +1894,14 @@ impl<T: Fn() -> String> Report<T> {
     ///Collects all nested logging events and prints them
     ///
     ///When this report is dropped, it will be printed to stdout.
+    ///The location of the caller is recorded, so that it can be
+    ///rendered if [`Report::show_locations`] is enabled.
+    ///
+    ///Unlike the message of a group, the message of this report is formatted when the report
+    ///is created, so that [`flush`] can print it from anywhere on the thread. The closure may
+    ///borrow local variables, so it can't be stored until then. The arguments of the message
+    ///are therefore evaluated immediately, even if the report is later skipped by
+    ///[`Report::on_error`] or stored by [`Sink::Ring`] instead of being printed.
     ///
     ///# Example
     ///```
@@ -231,15 +1911,98 @@ impl<T: Fn() -> String> Report<T> {
     ///info!("Complementary information");
     ///drop(report);
     ///```
+    ///
+    ///Every line of a message is rendered as a row of its own, both in the header and in events.
+    ///Continuation rows of events are aligned with the start of the message, and `\r\n` is
+    ///handled like `\n`:
+    ///```
+    ///# #[cfg(feature = "frame")] {
+    ///use report::{Report, Theme, info};
+    ///use std::sync::{Arc, Mutex};
+    ///
+    ///let output = Arc::new(Mutex::new(String::new()));
+    ///let sink = output.clone();
+    ///Report::set_writer(move |text| sink.lock().unwrap().push_str(text));
+    ///Report::set_theme(Theme::UNICODE);
+    ///Report::set_fallback_width(Some(24));
+    ///
+    ///for message in ["a\nb", "a\r\nb"] {
+    ///    let report = Report::log(move || message.to_string());
+    ///    info!("{message}");
+    ///    info!("{message}");
+    ///    drop(report);
+    ///}
+    ///
+    ///let expected = "\
+    ///╭──────────────────────╮
+    ///│ a                    │
+    ///│ b                    │
+    ///├─┬────────────────────┤
+    ///│ ├── info: a          │
+    ///│ │         b          │
+    ///│ ╰── info: a          │
+    ///│           b          │
+    ///╰──────────────────────╯
+    ///";
+    ///assert_eq!(*output.lock().unwrap(), expected.repeat(2));
+    ///# }
+    ///```
+    #[track_caller]
     pub fn log(message: T) -> Self {
+        let root = Root {
+            message: message(),
+            location: Location::caller(),
+            start: Instant::now(),
+            timing: false,
+            condition: Condition::Always,
+            sequence: SEQUENCE.fetch_add(1, Ordering::Relaxed),
+            target: Target::Default
+        };
+
         Self {
-            actions: ACTIONS.take(),
+            actions: ACTIONS.replace(SPARE.take()),
             message,
             active: ACTIVE.replace(true),
-            log: true
+            log: true,
+            level: None,
+            root: ROOT.replace(Some(root)),
+            start: Instant::now()
         }
     }
 
+    ///Collects all nested logging events and prints them to a file
+    ///
+    ///This is the same as [`Report::log`], but the report is written to the file at `path`
+    ///instead of the output of all other reports. The file is created, or truncated if it
+    ///already exists, when this report is created, and reports printed by [`flush`] are
+    ///appended to it. Since a file is not a terminal, the width is taken from
+    ///[`Report::set_fallback_width`] and no colors are used, even if they are forced with
+    ///[`Report::set_color`]. If the file can't be created,
+    ///a warning is printed and the report is written to stderr instead.
+    ///It is used by `#[log(file = "...", "...")]`.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, info};
+    ///
+    ///let path = std::env::temp_dir().join("report-log-to-file.txt");
+    ///Report::set_color(Some(true));
+    ///let report = Report::log_to_file(&path, || format!("Running task"));
+    ///info!("Complementary information");
+    ///drop(report);
+    ///
+    ///let output = std::fs::read_to_string(&path).unwrap();
+    ///assert!(output.contains("info: Complementary information"));
+    ///assert!(!output.contains('\x1b'));
+    ///```
+    #[track_caller]
+    pub fn log_to_file(path: impl AsRef<Path>, message: T) -> Self {
+        let report = Self::log(message);
+        let target = Target::file(path.as_ref());
+        ROOT.set(ROOT.take().map(|root| Root { target, ..root }));
+        report
+    }
+
     ///Collects all nested logging events and appends them to the
     ///preceding report
     /// 
@@ -254,31 +2017,223 @@ impl<T: Fn() -> String> Report<T> {
     ///info!("Complementary information");
     ///drop(report);
     ///```
+    ///
+    ///Groups can be nested arbitrarily deep:
+    ///```
+    ///use report::{Report, Level, info, warn};
+    ///
+    ///fn recurse(depth: usize) {
+    ///    let _group = Report::rec(move || format!("Depth {depth}"));
+    ///    if depth % 3 == 0 { warn!("Checkpoint at {depth}") }
+    ///    if depth < 64 { recurse(depth + 1) } else { info!("Reached the bottom") }
+    ///}
+    ///
+    ///let capture = Report::capture(|| {
+    ///    recurse(0);
+    ///    info!("Back at the top");
+    ///});
+    ///
+    ///let events: Vec<_> = capture.iter().collect();
+    ///assert_eq!(events.len(), 65 + 22 + 2);
+    ///for (depth, level, message) in &events {
+    ///    match level {
+    ///        None => assert_eq!(*message, format!("Depth {depth}")),
+    ///        Some(Level::Warn) => assert_eq!(*message, format!("Checkpoint at {}", depth - 1)),
+    ///        Some(_) => {}
+    ///    }
+    ///}
+    ///assert_eq!(events[events.len() - 2], (65, Some(Level::Info), "Reached the bottom"));
+    ///assert_eq!(events[events.len() - 1], (0, Some(Level::Info), "Back at the top"));
+    ///```
+    ///
+    ///Since empty groups are omitted, a group that only contains empty groups
+    ///is omitted as well:
+    ///```
+    ///use report::{Report, Level, info};
+    ///
+    ///fn nest(leaf: bool) {
+    ///    let _outer = Report::rec(|| format!("Outer"));
+    ///    let _middle = Report::rec(|| format!("Middle"));
+    ///    if leaf { info!("Leaf") }
+    ///    let _inner = Report::rec(|| format!("Inner"));
+    ///}
+    ///
+    ///let capture = Report::capture(|| nest(true));
+    ///assert_eq!(capture.iter().collect::<Vec<_>>(), [
+    ///    (0, None, "Outer"),
+    ///    (1, None, "Middle"),
+    ///    (2, Some(Level::Info), "Leaf")
+    ///]);
+    ///
+    ///let capture = Report::capture(|| nest(false));
+    ///assert_eq!(capture.iter().count(), 0);
+    ///```
+    #[track_caller]
     pub fn rec(message: T) -> Self {
         Self {
-            actions: ACTIONS.take(),
+            actions: ACTIONS.replace(SPARE.take()),
             message,
             active: ACTIVE.get(),
-            log: false
+            log: false,
+            level: None,
+            root: None,
+            start: Instant::now()
+        }
+    }
+
+    ///Renders the elapsed time in the header of this report
+    ///
+    ///This has the same effect as [`Report::show_durations`], but only applies
+    ///to this report. It is used by `#[log(timing, "...")]` and has no effect
+    ///on reports created by [`Report::rec`].
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, info};
+    ///
+    ///let report = Report::log(|| format!("Running task")).timed();
+    ///info!("Complementary information");
+    ///drop(report);
+    ///```
+    pub fn timed(self) -> Self {
+        if self.log {
+            ROOT.set(ROOT.take().map(|root| Root { timing: true, ..root }))
+        }
+        self
+    }
+
+    ///Only prints this report if it contains at least one event
+    ///
+    ///It is used by `#[log(on_event, "...")]` and has no effect on reports
+    ///created by [`Report::rec`]. Digests are emitted either way.
+    ///
+    ///# Example
+    ///```
+    ///use report::Report;
+    ///
+    ///let report = Report::log(|| format!("Nothing to see")).on_event();
+    ///drop(report);
+    ///```
+    pub fn on_event(self) -> Self {
+        self.print_if(Condition::OnEvent)
+    }
+
+    ///Only prints this report if it contains at least one error
+    ///
+    ///Errors in nested groups are taken into account as well. It is used by
+    ///`#[log(on_error, "...")]` and has no effect on reports created by [`Report::rec`].
+    ///Digests are emitted either way.
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, warn};
+    ///
+    ///let report = Report::log(|| format!("Running task")).on_error();
+    ///warn!("Not printed, since there is no error");
+    ///drop(report);
+    ///```
+    pub fn on_error(self) -> Self {
+        self.print_if(Condition::OnError)
+    }
+
+    ///Renders the header of this group with the label and style of a level
+    ///
+    ///This marks the nested events as the context of a warning or an error. It is
+    ///used by `#[report(warn, "...")]` and has no effect on reports created by [`Report::log`].
+    ///
+    ///# Example
+    ///```
+    ///use report::{Report, Level, info};
+    ///use std::sync::{Arc, Mutex};
+    ///
+    ///let output = Arc::new(Mutex::new(String::new()));
+    ///let sink = output.clone();
+    ///Report::set_writer(move |text| sink.lock().unwrap().push_str(text));
+    ///
+    ///let report = Report::log(|| format!("Running task"));
+    ///let group = Report::rec(|| format!("Opening file")).level(Level::Warn);
+    ///info!("Complementary information");
+    ///drop(group);
+    ///drop(report);
+    ///
+    ///assert!(output.lock().unwrap().contains("warning: Opening file"));
+    ///```
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    fn print_if(self, condition: Condition) -> Self {
+        if self.log {
+            ROOT.set(ROOT.take().map(|root| Root { condition, ..root }))
+        }
+        self
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        ACTIONS.set(take(&mut self.actions));
+        ACTIVE.set(self.active);
+        if let Some(root) = self.root.take() {
+            ROOT.set(root)
         }
     }
 }
 
 impl<T: Fn() -> String> Drop for Report<T> {
     fn drop(&mut self) {
+        #[cfg(feature = "signal")]
+        signal::check();
         let actions = ACTIONS.take();
+        let mut finished = None;
 
         if self.log {
-            Report::print((self.message)(), actions)
+            match ROOT.replace(self.root.take()) {
+                Some(root) => finished = Some((root, actions)),
+                None => SPARE.set(actions)
+            }
         } else if !actions.is_empty() {
             self.actions.push(Action::Report {
                 message: (self.message)(),
-                actions
+                actions,
+                elapsed: self.start.elapsed(),
+                level: self.level
             })
+        } else {
+            SPARE.set(actions)
         }
 
         ACTIVE.set(self.active);
         ACTIONS.set(take(&mut self.actions));
+
+        if let Some((root, actions)) = finished {
+            Report::finish(root, actions)
+        }
+    }
+}
+
+///Formats the digest as `✔ Running task — 0 errors, 2 warnings`
+///
+///The elapsed time is appended like `(1.2s)` if [`Report::show_durations`] is enabled.
+impl Display for Digest {
+    fn fmt(&self, formatter: &mut Formatter) -> FmtResult {
+        #[cfg(feature = "unicode")]
+        let mark = if self.errors == 0 { "✔" } else { "✘" };
+        #[cfg(not(feature = "unicode"))]
+        let mark = if self.errors == 0 { "+" } else { "x" };
+        let counts = Counts { errors: self.errors, warnings: self.warnings, infos: self.infos };
+        let duration = ReportConfig::get().durations.then_some(self.elapsed);
+        write!(formatter, "{mark} {} {DASH} {counts}{}", self.message, Action::get_duration(duration))
+    }
+}
+
+///Formats the counts as `0 errors, 2 warnings`
+impl Display for Counts {
+    fn fmt(&self, formatter: &mut Formatter) -> FmtResult {
+        let errors = if self.errors == 1 { "error" } else { "errors" };
+        let warnings = if self.warnings == 1 { "warning" } else { "warnings" };
+        write!(formatter, "{} {errors}, {} {warnings}", self.errors, self.warnings)
     }
 }
 
@@ -297,12 +2252,229 @@ impl Display for Error {
 }
 
 ///Constructs a new `Error` and moves the contex to thread local storage
-///by calling the [`error`](macro@error) macro.
+///by calling the [`error`](macro@error) macro, unless this was disabled with
+///[`Report::set_autolog_errors`]. The filter set with [`Report::set_module_filter`]
+///is not applied to these errors.
 impl<T: StdError> From<T> for Error {
+    #[track_caller]
     fn from(error: T) -> Self {
-        Report::error(format_args!("{error}"));
+        if ReportConfig::autolog_enabled() {
+            Report::error(format_args!("{error}"));
+        }
+        Error
+    }
+}
+
+///Opens a nested group until the returned guard is dropped
+///
+///This is the imperative counterpart of the [`report`](macro@report) attribute.
+///Events logged while the guard is alive are attached to the group, which is
+///folded into the enclosing report once the guard is dropped. Like any other
+///group, it is omitted if there are no events.
+///
+///# Example
+///```
+///use report::{group, log, info};
+///
+///#[log("Processing items")]
+///fn main() {
+///    for id in 0..3 {
+///        let _group = group(|| format!("Processing {id}"));
+///        info!("Item {id} is done");
+///    }
+///}
+///```
+#[track_caller]
+#[must_use = "the group is closed as soon as the guard is dropped"]
+pub fn group<T: Fn() -> String>(message: T) -> Report<T> {
+    Report::rec(message)
+}
+
+///Runs a closure in a nested group and turns a panic into an error event
+///
+///The closure is run inside of a group with the given message, just like with [`group`].
+///If it panics, the panic is caught, its message is logged as an error in this group,
+///and `Err` is returned instead of unwinding further. Groups and reports opened inside
+///of the closure are closed while unwinding, so the enclosing report stays intact.
+///The panic hook is still invoked as usual, which prints the panic to stderr by default.
+///
+///# Example
+///```
+///use report::{catch, Report, Level};
+///
+///let capture = Report::capture(|| {
+///    let result = catch(|| format!("Running plugin"), || -> i32 { panic!("Plugin failed") });
+///    assert!(result.is_err());
+///});
+///
+///assert_eq!(capture.iter().collect::<Vec<_>>(), [
+///    (0, None, "Running plugin"),
+///    (1, Some(Level::Error), "Panicked: Plugin failed")
+///]);
+///```
+#[track_caller]
+pub fn catch<T: Fn() -> String, R>(message: T, function: impl FnOnce() -> R) -> Result<R> {
+    let location = Location::caller();
+    let _group = Report::rec(message);
+    catch_unwind(AssertUnwindSafe(function)).map_err(|payload| {
+        let message = payload.downcast_ref::<&str>().copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("Unknown panic");
+        Report::error_at(location, format_args!("Panicked: {message}"));
         Error
+    })
+}
+
+///Returns an exit code for the most recently printed report
+///
+///The code is `2` if the report contained an error, `1` if it contained warnings but
+///no errors, and `0` otherwise. Events in nested groups are taken into account. Only
+///the most recent report created by [`log`](macro@log) is considered, regardless of the
+///thread it was printed on, and every call to [`flush`] counts as a report of its own.
+///If no report was printed yet, the code is `0`.
+///
+///The intended use is `std::process::exit(report::exit_code())` at the end of `main`.
+///Since [`std::process::exit`] doesn't run destructors, the report has to be dropped
+///before exiting, so it shouldn't be created in the same function.
+///
+///# Example
+///```
+///use report::{log, error};
+///
+///#[log("Running task")]
+///fn run() {
+///    error!("Something went wrong");
+///}
+///
+///fn main() {
+///    run();
+///    assert_eq!(report::exit_code(), 2);
+///}
+///```
+pub fn exit_code() -> i32 {
+    EXIT_CODE.load(Ordering::Relaxed)
+}
+
+///Prints the pending events of the active report without waiting for its drop
+///
+///The events collected so far are rendered under the message of the innermost
+///report created by [`log`](macro@log), as if the report was dropped, and are removed
+///from it afterwards. The report itself stays active and collects further events,
+///which makes it possible to print one report per iteration of a long-running loop.
+///Elapsed times and digests cover the time since the previous flush.
+///
+///If called inside of a group, only the events of this group are printed, without
+///the header of the group. Nothing is printed if there are no pending events or
+///if there is no active report.
+///
+///# Example
+///```
+///use report::{flush, log, info};
+///
+///#[log("Serving requests")]
+///fn main() {
+///    for id in 0..3 {
+///        info!("Handled request {id}");
+///        flush();
+///    }
+///}
+///```
+///
+///Every flush emits a digest, just like a dropped report:
+///```
+///use report::{flush, Report, info};
+///use std::sync::{Arc, Mutex};
+///
+///let lines = Arc::new(Mutex::new(Vec::new()));
+///let sink = lines.clone();
+///Report::set_digest_sink(move |line| sink.lock().unwrap().push(line.to_string()));
+///
+///let report = Report::log(|| format!("Serving requests"));
+///info!("Handled request");
+///flush();
+///flush();
+///assert_eq!(lines.lock().unwrap().len(), 1);
+///drop(report);
+///assert_eq!(lines.lock().unwrap().len(), 2);
+///```
+pub fn flush() {
+    let Some(mut root) = ROOT.take() else { return };
+
+    if !ACTIONS.with_borrow(Vec::is_empty) {
+        let actions = ACTIONS.replace(SPARE.take());
+        let start = replace(&mut root.start, Instant::now());
+        Report::finish(Root { message: root.message.clone(), target: root.target.clone(), start, ..root }, actions);
     }
+
+    ROOT.set(Some(root))
+}
+
+///Prints all reports that were queued on this thread
+///
+///With [`Order::Fifo`], reports that complete inside of another report are queued until
+///the outermost report completes. This function prints them immediately, in the order in
+///which they were created. Nothing is printed if the queue is empty.
+///
+///# Example
+///```
+///use report::{flush_all, Report, Order, log, info};
+///
+///Report::set_flush_order(Order::Fifo);
+///
+///#[log("Inner report")]
+///fn inner() {
+///    info!("Printed before the outer report completes");
+///}
+///
+///#[log("Outer report")]
+///fn outer() {
+///    inner();
+///    flush_all();
+///}
+///
+///outer();
+///```
+pub fn flush_all() {
+    let writer = ReportConfig::get().writer.clone();
+    Report::write_queue(writer.as_ref())
+}
+
+///Removes and returns all reports stored by [`Sink::Ring`]
+///
+///The reports are returned in the order in which they completed.
+///See [`Report::set_sink`] for an example.
+pub fn drain_roots() -> Vec<StoredReport> {
+    RING.lock().unwrap_or_else(PoisonError::into_inner).drain(..).collect()
+}
+
+///Logs a message with a level that is only known at runtime
+///
+///The level is an expression of type [`Level`], and events below the level set with
+///[`Report::set_level`] are discarded like with the other macros. The [`info`](macro@info),
+///[`warn`](macro@warn) and [`error`](macro@error) macros are shorthands for this macro,
+///and fields can be attached in the same way. The function counterpart is [`Report::log_event`].
+///
+///# Example
+///```
+///use report::{event, Report, Level};
+///
+///fn check(strict: bool) {
+///    let level = if strict { Level::Error } else { Level::Warn };
+///    event!(level, "Missing license field");
+///    event!(level, "Unknown key"; key = "edition2");
+///}
+///
+///let capture = Report::capture(|| check(true));
+///assert_eq!(capture.iter().collect::<Vec<_>>(), [
+///    (0, Some(Level::Error), "Missing license field"),
+///    (0, Some(Level::Error), "Unknown key")
+///]);
+///```
+#[macro_export]
+macro_rules! event {
+    ($level:expr, $($arg:tt)*) => {
+        report::__event!($level; $($arg)*)
+    };
 }
 
 ///Logs a message with the `info` prefix
@@ -314,14 +2486,32 @@ impl<T: StdError> From<T> for Error {
 ///let data = 42;
 ///info!("Data: {data}");
 ///```
+///
+///# Fields
+///
+///Key-value fields can be attached after a semicolon. They are rendered as
+///` path=Cargo.toml size=42` after the message and as a `fields` object in JSON.
+///Like in the `tracing` crate, values prefixed with `%` are formatted with `Display`
+///and values prefixed with `?` with `Debug`. Values without a prefix are formatted with
+///`Display`, and a variable can be used without a key to use its name as the key.
+///```
+///use report::info;
+///
+///let path = "Cargo.toml";
+///let size = 42;
+///let kind = Some("manifest");
+///info!("Opened file"; path = %path, size, kind = ?kind);
+///```
 #[macro_export]
 macro_rules! info {
     ($($arg:tt)*) => {
-        report::Report::info(format_args!($($arg)*))
+        report::event!(report::Level::Info, $($arg)*)
     };
 }
 
 ///Logs a message with the `warning` prefix
+///
+///Fields can be attached like with [`info`](macro@info).
 ///
  ///# Example
 ///```
@@ -329,15 +2519,18 @@ macro_rules! info {
 ///
 ///let data = 42;
 ///warn!("Warning: {data}");
+///warn!("Slow response"; millis = 1200);
 ///```
 #[macro_export]
 macro_rules! warn {
     ($($arg:tt)*) => {
-        report::Report::warn(format_args!($($arg)*))
+        report::event!(report::Level::Warn, $($arg)*)
     };
 }
 
 ///Logs a message with the `error` prefix
+///
+///Fields can be attached like with [`info`](macro@info).
 ///
  ///# Example
 ///```
@@ -345,20 +2538,21 @@ macro_rules! warn {
 ///
 ///let data = 42;
 ///error!("Error: {data}");
+///error!("Request failed"; status = 503);
 ///```
 #[macro_export]
 macro_rules! error {
     ($($arg:tt)*) => {
-        report::Report::error(format_args!($($arg)*))
+        report::event!(report::Level::Error, $($arg)*)
     };
 }
 
 ///Log error message and return from function
 ///
 ///This macro expands to the following code:
-///```
+///```ignore
 ///return Err({
-///    report::Report::error(format_args!(args));
+///    report::error!(args);
 ///    report::Error
 ///})
 ///```
@@ -375,8 +2569,44 @@ macro_rules! error {
 macro_rules! bail {
     ($($arg:tt)*) => {
         return Err({
-            report::Report::error(format_args!($($arg)*));
+            report::error!($($arg)*);
             report::Error
         })
     };
 }
+
+///Log error message and return from function if the condition is false
+///
+///This macro expands to the following code, so the format arguments are
+///only evaluated if the condition is false:
+///```ignore
+///if !(cond) {
+///    report::bail!(args)
+///}
+///```
+///
+///# Example
+///```
+///use report::{ensure, Report, Result, Level};
+///
+///fn function(value: i32) -> Result {
+///    ensure!(value > 0, "Expected a positive value, found {value}");
+///    Ok(())
+///}
+///
+///let capture = Report::capture(|| {
+///    assert!(function(1).is_ok());
+///    assert!(function(-1).is_err());
+///});
+///assert_eq!(capture.iter().collect::<Vec<_>>(), [
+///    (0, Some(Level::Error), "Expected a positive value, found -1")
+///]);
+///```
+#[macro_export]
+macro_rules! ensure {
+    ($cond:expr, $($arg:tt)*) => {
+        if !($cond) {
+            report::bail!($($arg)*)
+        }
+    };
+}
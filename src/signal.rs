@@ -0,0 +1,98 @@
+use std::cell::Cell;
+use std::io::{Error as IoError, Result as IoResult};
+use std::process::exit;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{sleep, Builder};
+use std::time::{Duration, Instant};
+use crate::{flush, flush_all};
+
+const POLL: Duration = Duration::from_millis(50);
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static EXITING: AtomicBool = AtomicBool::new(false);
+static WATCHDOG: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static HANDLER: Cell<bool> = const { Cell::new(false) };
+    static FLUSHED: Cell<bool> = const { Cell::new(false) };
+}
+
+///Prints the pending report of the current thread when the process is interrupted
+///
+///Without this handler, `Ctrl-C` terminates the process immediately and the reports
+///that are still active are lost. Once installed, the first `SIGINT` only sets a flag.
+///The next time the thread that installed the handler logs an event or drops a report,
+///the events of its innermost report are printed like with [`flush`], queued reports are
+///printed like with [`flush_all`], and the process exits with code `130`. Other threads
+///print their own pending events in the same way the next time they log, but keep running.
+///
+///If the thread that installed the handler is blocked or busy and doesn't log anything
+///within one second, a watchdog thread exits the process with code `130` without
+///printing its report. A second `SIGINT` terminates the process immediately.
+///
+///Since thread local storage can't be accessed from a signal handler, the report is
+///never rendered inside of the handler itself, which also means that the handler is
+///never reentered while a report is rendered. Only the events that were logged since the
+///innermost open group was created are printed, since the events of the enclosing groups
+///are stored in their guards until the inner group is dropped. Destructors don't run when
+///the process exits. The handler replaces any previously installed handler for `SIGINT`.
+///
+///The flag is checked whenever an event is logged or a report is dropped.
+///
+///# Example
+///```
+///use report::{install_signal_handler, log, info};
+///
+///#[log("Running task")]
+///fn main() {
+///    install_signal_handler().unwrap();
+///    for step in 0..3 {
+///        info!("Finished step {step}");
+///    }
+///}
+///```
+pub fn install_signal_handler() -> IoResult<()> {
+    if !WATCHDOG.swap(true, Ordering::Relaxed) {
+        Builder::new().name(String::from("report-watchdog")).spawn(watch)?;
+    }
+    let handler = handle as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    if unsafe { libc::signal(libc::SIGINT, handler) } == libc::SIG_ERR {
+        return Err(IoError::last_os_error())
+    }
+    HANDLER.set(true);
+    Ok(())
+}
+
+pub(crate) fn check() {
+    if !INTERRUPTED.load(Ordering::Relaxed) || FLUSHED.replace(true) { return }
+
+    if HANDLER.get() {
+        EXITING.store(true, Ordering::Relaxed);
+        flush();
+        flush_all();
+        exit(130)
+    }
+
+    flush();
+    flush_all()
+}
+
+fn watch() {
+    while !INTERRUPTED.load(Ordering::Relaxed) {
+        sleep(POLL)
+    }
+
+    let start = Instant::now();
+    while start.elapsed() < TIMEOUT || EXITING.load(Ordering::Relaxed) {
+        sleep(POLL)
+    }
+
+    exit(130)
+}
+
+extern "C" fn handle(_: libc::c_int) {
+    if INTERRUPTED.swap(true, Ordering::Relaxed) {
+        unsafe { libc::_exit(130) }
+    }
+}
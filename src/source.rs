@@ -0,0 +1,110 @@
+use std::error::Error as StdError;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::result::Result as StdResult;
+use crate::{Error, Report, ReportConfig};
+
+type Source = Box<dyn StdError + Send + Sync + 'static>;
+
+///Result type that retains the source of its error
+///
+///See [`SourceError`].
+pub type SourceResult<T = ()> = StdResult<T, SourceError>;
+
+///Error type that retains its source
+///
+///Like [`Error`], this type calls the [`error`](macro@crate::error) macro in its `From`
+///implementation, so the error is logged to the active report. Unlike [`Error`], the
+///original error is kept, so callers can inspect it with [`SourceError::downcast_ref`],
+///for example to match on an [`io::ErrorKind`](std::io::ErrorKind). This costs an allocation
+///per error, so [`Error`] remains the default.
+///
+///An [`Error`] can be converted into this type, for example by the `?` operator, in which
+///case there is no source. This type can be converted into an [`Error`] without logging
+///the error again.
+///
+///# Example
+///```
+///use report::{Report, SourceResult, Level};
+///use std::fs::File;
+///use std::io::{self, ErrorKind};
+///
+///fn open(path: &str) -> SourceResult<File> {
+///    Ok(File::open(path)?)
+///}
+///
+///let capture = Report::capture(|| {
+///    let error = open("missing.txt").unwrap_err();
+///    let kind = error.downcast_ref::<io::Error>().map(io::Error::kind);
+///    assert_eq!(kind, Some(ErrorKind::NotFound));
+///});
+///
+///assert_eq!(capture.iter().next().map(|(_, level, _)| level), Some(Some(Level::Error)));
+///```
+pub struct SourceError {
+    source: Option<Source>
+}
+
+impl SourceError {
+
+    ///Returns a reference to the source of this error, if there is one
+    pub fn get_ref(&self) -> Option<&(dyn StdError + Send + Sync + 'static)> {
+        self.source.as_deref()
+    }
+
+    ///Returns a reference to the source of this error, if it is of type `E`
+    pub fn downcast_ref<E: StdError + 'static>(&self) -> Option<&E> {
+        self.source.as_ref()?.downcast_ref()
+    }
+
+    ///Consumes the error and returns its source, if there is one
+    pub fn into_inner(self) -> Option<Source> {
+        self.source
+    }
+}
+
+///Calls the [`error`](macro@crate::error) macro and retains the error as the source
+///
+///The error is not logged if this was disabled with [`Report::set_autolog_errors`].
+impl<T: StdError + Send + Sync + 'static> From<T> for SourceError {
+    #[track_caller]
+    fn from(error: T) -> Self {
+        if ReportConfig::autolog_enabled() {
+            Report::error(format_args!("{error}"));
+        }
+        SourceError { source: Some(Box::new(error)) }
+    }
+}
+
+///Converts an error that was already logged, without a source
+impl From<Error> for SourceError {
+    fn from(_: Error) -> Self {
+        SourceError { source: None }
+    }
+}
+
+///Discards the source without logging the error again
+impl From<SourceError> for Error {
+    fn from(_: SourceError) -> Self {
+        Error
+    }
+}
+
+///Formats the source, or the same text as [`Error`] if there is none
+impl Display for SourceError {
+    fn fmt(&self, formatter: &mut Formatter) -> FmtResult {
+        match &self.source {
+            Some(source) => Display::fmt(source, formatter),
+            None => Display::fmt(&Error, formatter)
+        }
+    }
+}
+
+///Formats the source, or the same text as [`Error`] if there is none
+impl Debug for SourceError {
+    fn fmt(&self, formatter: &mut Formatter) -> FmtResult {
+        match &self.source {
+            Some(source) => Debug::fmt(source, formatter),
+            None => Debug::fmt(&Error, formatter)
+        }
+    }
+}
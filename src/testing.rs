@@ -0,0 +1,73 @@
+use crate::{Action, Root, ACTIONS, ACTIVE, OUTPUT, ROOT};
+
+struct State {
+    actions: Vec<Action>,
+    active: bool,
+    root: Option<Root>,
+    output: Option<String>
+}
+
+///Runs the closure and returns everything that reports printed in the meantime
+///
+///The output of reports on the current thread is written to a buffer instead of stdout
+///while the closure runs. Since the buffer is not a terminal, frames are only drawn with a
+///[fallback width](crate::Report::set_fallback_width) and colors are only used if they are
+///[forced](crate::Report::set_color), which makes the output independent of the environment.
+///The closure starts without an active report, so events are not attached to the caller.
+///The previous state is restored afterwards, even if the closure panics.
+///
+///# Example
+///```
+///# #[cfg(all(feature = "unicode", feature = "frame"))] {
+///use report::{Report, info, warn};
+///use report::testing::with_capture;
+///
+///Report::set_fallback_width(Some(32));
+///let output = with_capture(|| {
+///    let _report = Report::log(|| format!("Running task"));
+///    info!("First event");
+///    warn!("Second event");
+///});
+///
+///assert_eq!(output, "\
+///╭──────────────────────────────╮
+///│ Running task                 │
+///├─┬────────────────────────────┤
+///│ ├── info: First event        │
+///│ ╰── warning: Second event    │
+///╰──────────────────────────────╯
+///");
+///# }
+///```
+///
+///The state is restored if the closure panics:
+///```
+///use report::info;
+///use report::testing::with_capture;
+///use std::panic::catch_unwind;
+///
+///assert!(catch_unwind(|| with_capture(|| panic!("Failure"))).is_err());
+///assert_eq!(with_capture(|| info!("Printed immediately")), "info: Printed immediately\n");
+///```
+pub fn with_capture(function: impl FnOnce()) -> String {
+    let state = State {
+        actions: ACTIONS.take(),
+        active: ACTIVE.replace(false),
+        root: ROOT.take(),
+        output: OUTPUT.replace(Some(String::new()))
+    };
+
+    function();
+    let output = OUTPUT.take().unwrap_or_default();
+    drop(state);
+    output
+}
+
+impl Drop for State {
+    fn drop(&mut self) {
+        ACTIONS.set(std::mem::take(&mut self.actions));
+        ACTIVE.set(self.active);
+        ROOT.set(self.root.take());
+        OUTPUT.set(self.output.take());
+    }
+}
@@ -0,0 +1,122 @@
+use std::borrow::Cow;
+use console::measure_text_width;
+
+///Glyphs used to draw the tree and the frame of a report
+///
+///The default theme is [`Theme::UNICODE`] if the `unicode` feature is enabled
+///and [`Theme::ASCII`] otherwise. The branch and indent glyphs should have the
+///same display width, so that nested events line up. If the width differs from
+///the one set with [`Report::set_indent`](crate::Report::set_indent), the second
+///character of a glyph is repeated or removed to fit.
+///
+///# Example
+///```
+///use report::{Report, Theme};
+///
+///Report::set_theme(Theme {
+///    branch: "+-- ",
+///    last_branch: "`-- ",
+///    ..Theme::ASCII
+///});
+///```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    ///Upper left corner of the frame
+    pub top_left: &'static str,
+    ///Upper right corner of the frame
+    pub top_right: &'static str,
+    ///Lower left corner of the frame
+    pub bottom_left: &'static str,
+    ///Lower right corner of the frame
+    pub bottom_right: &'static str,
+    ///Horizontal border of the frame
+    pub horizontal: &'static str,
+    ///Vertical border of the frame
+    pub vertical: &'static str,
+    ///Left end of the row between the header and the events
+    pub separator_left: &'static str,
+    ///Junction of the separator row, which connects to the first event
+    pub separator_junction: &'static str,
+    ///Right end of the row between the header and the events
+    pub separator_right: &'static str,
+    ///Connection of every event except the last one in a group
+    pub branch: &'static str,
+    ///Connection of the last event in a group
+    pub last_branch: &'static str,
+    ///Indentation below an event that is followed by siblings
+    pub indent: &'static str,
+    ///Indentation below the last event in a group
+    pub last_indent: &'static str
+}
+
+impl Theme {
+
+    ///Unicode box drawing characters
+    pub const UNICODE: Theme = Theme {
+        top_left: "╭",
+        top_right: "╮",
+        bottom_left: "╰",
+        bottom_right: "╯",
+        horizontal: "─",
+        vertical: "│",
+        separator_left: "├",
+        separator_junction: "┬",
+        separator_right: "┤",
+        branch: "├── ",
+        last_branch: "╰── ",
+        indent: "│   ",
+        last_indent: "    "
+    };
+
+    ///Plain ASCII characters
+    pub const ASCII: Theme = Theme {
+        top_left: "+",
+        top_right: "+",
+        bottom_left: "+",
+        bottom_right: "+",
+        horizontal: "-",
+        vertical: "|",
+        separator_left: "+",
+        separator_junction: "-",
+        separator_right: "+",
+        branch: "|-- ",
+        last_branch: "\\-- ",
+        indent: "|   ",
+        last_indent: "    "
+    };
+
+    pub(crate) const fn new() -> Self {
+        #[cfg(feature = "unicode")]
+        return Theme::UNICODE;
+        #[cfg(not(feature = "unicode"))]
+        return Theme::ASCII;
+    }
+
+    pub(crate) fn get_connection(&self, last: bool, width: usize) -> Cow<'static, str> {
+        Theme::resize(if last { self.last_branch } else { self.branch }, width)
+    }
+
+    pub(crate) fn get_indent(&self, last: bool, width: usize) -> Cow<'static, str> {
+        Theme::resize(if last { self.last_indent } else { self.indent }, width)
+    }
+
+    fn resize(glyph: &'static str, width: usize) -> Cow<'static, str> {
+        let mut chars = glyph.chars();
+        let (Some(first), Some(fill), Some(last)) = (chars.next(), chars.next(), chars.last()) else {
+            return Cow::Borrowed(glyph)
+        };
+
+        if measure_text_width(glyph) == width.max(2) {
+            return Cow::Borrowed(glyph)
+        }
+
+        let fill = String::from(fill).repeat(width.saturating_sub(2));
+        Cow::Owned(format!("{first}{fill}{last}"))
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::new()
+    }
+}